@@ -0,0 +1,265 @@
+use std::{collections::HashMap, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes, Span};
+use parser::types::{ImplDecl, NodeKind, Program, SharedDecl, StructDecl};
+
+use crate::declarations::Declarations;
+
+/// Static pass validating every `impl shared ... on ...` against the
+/// interface it implements, before any code runs.
+pub struct ConformanceChecker<'a> {
+    structs: HashMap<&'a str, &'a Rc<StructDecl>>,
+    shared: HashMap<&'a str, &'a Rc<SharedDecl>>,
+    // members added to each struct by the interfaces implemented so far
+    implemented: HashMap<&'a str, Vec<(&'a str, &'a str)>>,
+    // impls of earlier programs, already checked
+    earlier: &'a [Rc<ImplDecl>],
+    diagnostics: Vec<CompilerError>,
+}
+
+impl<'a> ConformanceChecker<'a> {
+    /// `declarations` are the items `program` sees besides its own.
+    pub fn new(program: &'a Program, declarations: &'a Declarations) -> ConformanceChecker<'a> {
+        let mut structs: HashMap<&str, &Rc<StructDecl>> = declarations
+            .structs
+            .iter()
+            .map(|s| (s.name.as_str(), s))
+            .collect();
+        let mut shared: HashMap<&str, &Rc<SharedDecl>> = declarations
+            .shared
+            .iter()
+            .map(|s| (s.name.as_str(), s))
+            .collect();
+        for node in program.tree.iter() {
+            match &node.kind {
+                NodeKind::Struct(s) => {
+                    structs.insert(s.name.as_str(), s);
+                }
+                NodeKind::Shared(s) => {
+                    shared.insert(s.name.as_str(), s);
+                }
+                _ => {}
+            }
+        }
+
+        ConformanceChecker {
+            structs,
+            shared,
+            implemented: HashMap::new(),
+            earlier: &declarations.impls,
+            diagnostics: vec![],
+        }
+    }
+
+    /// Checks every top level impl, collecting all diagnostics instead of
+    /// stopping at the first one.
+    pub fn check(mut self, program: &'a Program) -> Result<(), Vec<CompilerError>> {
+        // what earlier impls added is taken, their problems were reported
+        for declaration in self.earlier.iter() {
+            self.check_impl(declaration);
+        }
+        self.diagnostics.clear();
+
+        for node in program.tree.iter() {
+            if let NodeKind::Impl(declaration) = &node.kind {
+                self.check_impl(declaration);
+            }
+        }
+
+        match self.diagnostics.is_empty() {
+            true => Ok(()),
+            false => Err(self.diagnostics),
+        }
+    }
+
+    fn check_impl(&mut self, declaration: &'a ImplDecl) {
+        let span = declaration.span;
+        let Some(&shared) = self.shared.get(declaration.shared.as_str()) else {
+            self.error(
+                span,
+                format!("Undefined shared interface '{}'", declaration.shared),
+            );
+            return;
+        };
+
+        let Some(&target) = self.structs.get(declaration.target.as_str()) else {
+            match self.shared.contains_key(declaration.target.as_str()) {
+                true => self.report(
+                    semantic_error(
+                        span,
+                        format!(
+                            "Cannot implement '{}' on shared '{}'",
+                            shared.name, declaration.target
+                        ),
+                    )
+                    .with_help("shared interfaces can only be implemented on structs"),
+                ),
+                false => self.error(span, format!("Undefined struct '{}'", declaration.target)),
+            }
+            return;
+        };
+
+        for method in declaration.methods.iter() {
+            match shared.methods.iter().find(|m| m.name == method.name) {
+                Some(required) if required.params.len() != method.params.len() => self.error(
+                    method.span,
+                    format!(
+                        "Method '{}' of shared '{}' takes {} parameter(s) but {} were given",
+                        method.name,
+                        shared.name,
+                        required.params.len(),
+                        method.params.len()
+                    ),
+                ),
+                Some(_) => {}
+                None => self.report(
+                    semantic_error(
+                        method.span,
+                        format!(
+                            "'{}' is not a method of shared '{}'",
+                            method.name, shared.name
+                        ),
+                    )
+                    .with_help("methods outside the interface belong in the struct declaration"),
+                ),
+            }
+        }
+
+        for field in declaration.overrides.iter() {
+            if !shared.fields.iter().any(|f| f.name == field.name) {
+                self.error(
+                    field.span,
+                    format!(
+                        "'{}' is not a field of shared '{}'",
+                        field.name, shared.name
+                    ),
+                );
+            }
+        }
+
+        for required in shared.methods.iter().filter(|m| m.body.is_none()) {
+            if !declaration.methods.iter().any(|m| m.name == required.name) {
+                self.error(
+                    span,
+                    format!(
+                        "Missing method '{}' required by shared '{}'",
+                        required.name, shared.name
+                    ),
+                );
+            }
+        }
+
+        self.check_members(declaration, target, shared);
+    }
+
+    /// Members of an interface may not clash with the struct's own members or
+    /// with a member of another interface implemented on the same struct.
+    fn check_members(
+        &mut self,
+        declaration: &'a ImplDecl,
+        target: &'a StructDecl,
+        shared: &'a SharedDecl,
+    ) {
+        let members = shared
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .chain(shared.methods.iter().map(|m| m.name.as_str()));
+
+        let mut errors = vec![];
+        let implemented = self.implemented.entry(target.name.as_str()).or_default();
+        if implemented.iter().any(|(_, s)| *s == shared.name) {
+            errors.push(format!(
+                "'{}' already implements '{}'",
+                target.name, shared.name
+            ));
+        } else {
+            for member in members {
+                let own = target.fields.iter().any(|f| f.name == member)
+                    || target.methods.iter().any(|m| m.name == member);
+                if own {
+                    errors.push(format!(
+                        "'{member}' of shared '{}' conflicts with a member of '{}'",
+                        shared.name, target.name
+                    ));
+                } else if let Some((_, other)) = implemented.iter().find(|(m, _)| *m == member) {
+                    errors.push(format!(
+                        "'{member}' is declared by both shared '{other}' and shared '{}' on '{}'",
+                        shared.name, target.name
+                    ));
+                } else {
+                    implemented.push((member, shared.name.as_str()));
+                }
+            }
+        }
+
+        for message in errors {
+            self.error(declaration.span, message);
+        }
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.report(semantic_error(span, message));
+    }
+
+    fn report(&mut self, error: CompilerError) {
+        self.diagnostics.push(error);
+    }
+}
+
+/// Runs the conformance pass over a parsed program.
+pub fn check_conformance(
+    program: &Program,
+    declarations: &Declarations,
+) -> Result<(), Vec<CompilerError>> {
+    ConformanceChecker::new(program, declarations).check(program)
+}
+
+fn semantic_error(span: Span, message: String) -> CompilerError {
+    CompilerError::at(span, message, ErrorTypes::SemanticError)
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::scanner::Scanner;
+    use parser::parser::Parser;
+
+    use super::check_conformance;
+    use crate::declarations::Declarations;
+
+    /// Messages of every conformance error in `source`.
+    fn errors(source: &str) -> Vec<String> {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+        let program = Parser::new(&scanner.tokens).parse_program().unwrap();
+        match check_conformance(&program, &Declarations::new()) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|e| e.message.clone()).collect(),
+        }
+    }
+
+    const SHAPE: &str =
+        "shared Shape { sides: number = 0; fn area() => number; } struct Square {} ";
+
+    #[test]
+    fn conforming_impls_pass() {
+        let source =
+            format!("{SHAPE} impl shared Shape on Square {{ sides = 4; fn area() {{ 1 }} }}");
+        assert!(errors(&source).is_empty());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let source = format!(
+            "{SHAPE} impl shared Shape on Square {{ colour = 1; }} impl shared Shape on Circle {{}}"
+        );
+        assert_eq!(
+            errors(&source),
+            [
+                "'colour' is not a field of shared 'Shape'",
+                "Missing method 'area' required by shared 'Shape'",
+                "Undefined struct 'Circle'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::rc::Rc;
+
+use parser::types::{EnumDecl, Function, ImplDecl, NodeKind, Program, SharedDecl, StructDecl};
+
+/// Top level items a program can refer to without declaring them itself: the
+/// built-in `Result` enum, and whatever earlier programs declared when it
+/// continues them, like a REPL entry does the entries before it.
+#[derive(Debug, Clone)]
+pub struct Declarations {
+    pub structs: Vec<Rc<StructDecl>>,
+    pub shared: Vec<Rc<SharedDecl>>,
+    pub enums: Vec<Rc<EnumDecl>>,
+    pub impls: Vec<Rc<ImplDecl>>,
+    pub functions: Vec<Rc<Function>>,
+}
+
+impl Default for Declarations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Declarations {
+    /// The declarations every program sees.
+    pub fn new() -> Declarations {
+        Declarations {
+            structs: vec![],
+            shared: vec![],
+            enums: vec![Rc::new(EnumDecl::result())],
+            impls: vec![],
+            functions: vec![],
+        }
+    }
+
+    /// Adds the top level items of `program`, so programs checked after it
+    /// see them. A later item shadows an earlier one of the same name.
+    pub fn extend(&mut self, program: &Program) {
+        for node in program.tree.iter() {
+            match &node.kind {
+                NodeKind::Struct(s) => self.structs.push(s.clone()),
+                NodeKind::Shared(s) => self.shared.push(s.clone()),
+                NodeKind::Enum(e) => self.enums.push(e.clone()),
+                NodeKind::Impl(i) => self.impls.push(i.clone()),
+                NodeKind::Function(f) => self.functions.push(f.clone()),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes};
+use parser::types::{EnumDecl, MatchArm, Node, NodeKind, PatternKind, Program, VariantPattern};
+
+use crate::declarations::Declarations;
+
+/// Warns about `match` expressions over an enum that leave variants
+/// unhandled. A match counts as being over an enum when every arm is a
+/// pattern on one of its variants.
+pub struct ExhaustivenessChecker<'a> {
+    enums: HashMap<&'a str, &'a Rc<EnumDecl>>,
+    warnings: Vec<CompilerError>,
+}
+
+impl<'a> ExhaustivenessChecker<'a> {
+    /// `declarations` are the items `program` sees besides its own.
+    pub fn new(program: &'a Program, declarations: &'a Declarations) -> ExhaustivenessChecker<'a> {
+        let mut enums: HashMap<&str, &Rc<EnumDecl>> = declarations
+            .enums
+            .iter()
+            .map(|e| (e.name.as_str(), e))
+            .collect();
+        for node in program.tree.iter() {
+            if let NodeKind::Enum(e) = &node.kind {
+                enums.insert(e.name.as_str(), e);
+            }
+        }
+
+        ExhaustivenessChecker {
+            enums,
+            warnings: vec![],
+        }
+    }
+
+    pub fn check(mut self, program: &'a Program) -> Vec<CompilerError> {
+        for node in program.tree.iter() {
+            self.visit(node);
+        }
+        self.warnings
+    }
+
+    fn visit(&mut self, node: &'a Node) {
+        if let NodeKind::Match { arms, .. } = &node.kind {
+            self.check_match(node, arms);
+        }
+        for child in node.children() {
+            self.visit(child);
+        }
+    }
+
+    fn check_match(&mut self, node: &Node, arms: &[MatchArm]) {
+        // an unguarded catch all arm handles every remaining value
+        if arms
+            .iter()
+            .any(|arm| arm.guard.is_none() && arm.pattern.is_irrefutable())
+        {
+            return;
+        }
+
+        let mut enum_name = None;
+        for arm in arms.iter() {
+            match &arm.pattern.kind {
+                PatternKind::Variant { enum_name: e, .. }
+                    if enum_name.is_none() || enum_name == Some(e) =>
+                {
+                    enum_name = Some(e)
+                }
+                _ => return,
+            }
+        }
+        let Some(declaration) = enum_name.and_then(|e| self.enums.get(e.as_str())) else {
+            return;
+        };
+
+        let missing: Vec<String> = declaration
+            .variants
+            .iter()
+            .filter(|v| !arms.iter().any(|arm| covers(arm, &v.name)))
+            .map(|v| format!("{}::{}", declaration.name, v.name))
+            .collect();
+
+        if !missing.is_empty() {
+            self.warnings.push(
+                CompilerError::at(
+                    node.span,
+                    format!(
+                        "Non-exhaustive match over '{}', missing {}",
+                        declaration.name,
+                        missing.join(", ")
+                    ),
+                    ErrorTypes::SemanticError,
+                )
+                .with_help("add an arm for each missing variant or a `_` arm")
+                .as_warning(),
+            );
+        }
+    }
+}
+
+/// Whether an arm matches every value of the variant `name`, which requires
+/// no guard and only catch all patterns for the variant's fields.
+fn covers(arm: &MatchArm, name: &str) -> bool {
+    let PatternKind::Variant {
+        variant, fields, ..
+    } = &arm.pattern.kind
+    else {
+        return false;
+    };
+
+    let fields_irrefutable = match fields {
+        VariantPattern::Unit => true,
+        VariantPattern::Tuple(patterns) => patterns.iter().all(|p| p.is_irrefutable()),
+        VariantPattern::Struct(fields) => fields.iter().all(|(_, p)| p.is_irrefutable()),
+    };
+    variant == name && arm.guard.is_none() && fields_irrefutable
+}
+
+/// Runs the exhaustiveness pass over a parsed program, returning warnings.
+pub fn check_exhaustiveness(program: &Program, declarations: &Declarations) -> Vec<CompilerError> {
+    ExhaustivenessChecker::new(program, declarations).check(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::scanner::Scanner;
+    use parser::parser::Parser;
+
+    use super::check_exhaustiveness;
+    use crate::declarations::Declarations;
+
+    const LIGHT: &str = "enum Light { Red, Amber, Green(number) } ";
+
+    /// Messages of every warning for a match over `arms` on a `Light`.
+    fn warnings(arms: &str) -> Vec<String> {
+        let source = format!("{LIGHT} fn go(light) {{ match light {{ {arms} }} }}");
+        let mut scanner = Scanner::new(&source);
+        scanner.scan_tokens().unwrap();
+        let program = Parser::new(&scanner.tokens).parse_program().unwrap();
+        check_exhaustiveness(&program, &Declarations::new())
+            .into_iter()
+            .inspect(|w| assert!(w.is_warning()))
+            .map(|w| w.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn matches_covering_every_variant_pass() {
+        assert!(warnings("Light::Red => 1, Light::Amber => 2, Light::Green(_) => 3,").is_empty());
+        assert!(warnings("Light::Red => 1, _ => 2,").is_empty());
+        assert!(warnings("Light::Red => 1, other => 2,").is_empty());
+    }
+
+    #[test]
+    fn missing_variants_are_warnings() {
+        assert_eq!(
+            warnings("Light::Red => 1, Light::Amber => 2,"),
+            ["Non-exhaustive match over 'Light', missing Light::Green"]
+        );
+    }
+
+    #[test]
+    fn guarded_or_refutable_arms_do_not_cover_a_variant() {
+        let arms = "Light::Red => 1, Light::Amber if true => 2, Light::Green(0) => 3,";
+        assert_eq!(
+            warnings(arms),
+            ["Non-exhaustive match over 'Light', missing Light::Amber, Light::Green"]
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod conformance;
+pub mod declarations;
+pub mod exhaustiveness;
+pub mod typecheck;
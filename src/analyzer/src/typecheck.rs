@@ -0,0 +1,891 @@
+use std::{collections::HashMap, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes, Span};
+use parser::types::{
+    Block, EnumDecl, Field, Function, ImplDecl, Literal, MatchArm, Node, NodeKind, Operator, Param,
+    PatternKind, Program, SharedDecl, StructDecl, Type, UnaryOperator, VariantKind, VariantPattern,
+};
+
+use crate::declarations::Declarations;
+
+/// Gradual type checker. Annotated bindings, parameters, fields and return
+/// types are checked wherever the type of a value is known statically.
+/// Everything else is dynamic and left to the interpreter. The one check
+/// that applies to unannotated code is that only `let mut` variables are
+/// assigned.
+pub struct TypeChecker<'a> {
+    structs: HashMap<&'a str, &'a Rc<StructDecl>>,
+    shared: HashMap<&'a str, &'a Rc<SharedDecl>>,
+    enums: HashMap<&'a str, &'a Rc<EnumDecl>>,
+    impls: Vec<&'a Rc<ImplDecl>>,
+    scopes: Vec<HashMap<&'a str, Binding<'a>>>,
+    // declared return type of each function being checked, innermost last
+    returns: Vec<Option<Type>>,
+    // struct whose methods are being checked, what `this` refers to
+    this: Option<&'a str>,
+    diagnostics: Vec<CompilerError>,
+}
+
+/// What a name refers to. A variable's type is `None` when it is dynamic.
+#[derive(Clone)]
+enum Binding<'a> {
+    Variable(Option<Type>),
+    /// `let mut`, the only kind of binding that can be assigned.
+    Mutable(Option<Type>),
+    Function(&'a Function),
+}
+
+impl<'a> TypeChecker<'a> {
+    /// `declarations` are the items `program` sees besides its own.
+    pub fn new(program: &'a Program, declarations: &'a Declarations) -> TypeChecker<'a> {
+        let mut checker = TypeChecker {
+            structs: HashMap::new(),
+            shared: HashMap::new(),
+            enums: HashMap::new(),
+            impls: vec![],
+            scopes: vec![HashMap::new()],
+            returns: vec![],
+            this: None,
+            diagnostics: vec![],
+        };
+        for s in declarations.structs.iter() {
+            checker.structs.insert(s.name.as_str(), s);
+        }
+        for s in declarations.shared.iter() {
+            checker.shared.insert(s.name.as_str(), s);
+        }
+        for e in declarations.enums.iter() {
+            checker.enums.insert(e.name.as_str(), e);
+        }
+        checker.impls.extend(declarations.impls.iter());
+        for f in declarations.functions.iter() {
+            checker.define(&f.name, Binding::Function(f));
+        }
+
+        // functions can be called before their declaration
+        for node in program.tree.iter() {
+            match &node.kind {
+                NodeKind::Struct(s) => {
+                    checker.structs.insert(s.name.as_str(), s);
+                }
+                NodeKind::Shared(s) => {
+                    checker.shared.insert(s.name.as_str(), s);
+                }
+                NodeKind::Enum(e) => {
+                    checker.enums.insert(e.name.as_str(), e);
+                }
+                NodeKind::Impl(i) => checker.impls.push(i),
+                NodeKind::Function(f) => checker.define(&f.name, Binding::Function(f)),
+                _ => {}
+            }
+        }
+        checker
+    }
+
+    pub fn check(mut self, program: &'a Program) -> Result<(), Vec<CompilerError>> {
+        for node in program.tree.iter() {
+            self.expr(node);
+        }
+
+        match self.diagnostics.is_empty() {
+            true => Ok(()),
+            false => Err(self.diagnostics),
+        }
+    }
+
+    /// Checks `node` and returns its static type, `None` when it is only
+    /// known at runtime.
+    fn expr(&mut self, node: &'a Node) -> Option<Type> {
+        match &node.kind {
+            NodeKind::Literal(literal) => Some(literal_type(literal)),
+            NodeKind::Name(name) => match self.lookup(name)? {
+                Binding::Variable(t) | Binding::Mutable(t) => self.resolve(&t?),
+                Binding::Function(_) => None,
+            },
+            NodeKind::This => self.this.map(|s| Type::Named(s.to_string())),
+            NodeKind::Declaration(declaration) => {
+                let value = self.expr(&declaration.value);
+                if let Some(annotation) = &declaration.annotation {
+                    self.check_annotation(annotation, node.span);
+                    if let Some(expected) = self.resolve(annotation) {
+                        let what = format!("'{}'", declaration.symbol);
+                        self.expect(&expected, &value, declaration.value.span, &what);
+                    }
+                }
+                // `:=` keeps whatever type the value is known to have
+                let t = match declaration.inferred {
+                    true => value,
+                    false => declaration.annotation.clone(),
+                };
+                let binding = match declaration.mutable {
+                    true => Binding::Mutable(t),
+                    false => Binding::Variable(t),
+                };
+                self.define(&declaration.symbol, binding);
+                None
+            }
+            NodeKind::Try(value) => {
+                self.expr(value);
+                None
+            }
+            NodeKind::Assign { target, op, value } => {
+                let found = self.expr(value);
+                match &target.kind {
+                    NodeKind::Name(name) => self.assign(name, op, &found, node.span, value.span),
+                    NodeKind::Get { object, field, .. } => {
+                        let object = self.expr(object);
+                        if let (Some(Type::Named(name)), None) = (object, op) {
+                            if let Some(field) = self.field(&name, field) {
+                                self.expect_field(field, &found, value.span);
+                            }
+                        }
+                    }
+                    _ => {
+                        self.expr(target);
+                    }
+                }
+                op.is_none().then_some(found).flatten()
+            }
+            NodeKind::BinaryExpr {
+                op: Operator::Coalesce,
+                lhs,
+                rhs,
+            } => {
+                // `p.name ?? "anon"` is a string when the optional field is
+                if let NodeKind::Get { object, field, .. } = &lhs.kind {
+                    let l = self.get(object, field);
+                    let r = self.expr(rhs);
+                    return match l {
+                        Some((t, true)) => (r == Some(t.clone())).then_some(t),
+                        Some((t, false)) => Some(t),
+                        None => None,
+                    };
+                }
+                let l = self.expr(lhs);
+                let r = self.expr(rhs);
+                operator_type(&Operator::Coalesce, l, r)
+            }
+            NodeKind::BinaryExpr { op, lhs, rhs } => {
+                let l = self.expr(lhs);
+                let r = self.expr(rhs);
+                operator_type(op, l, r)
+            }
+            NodeKind::UnaryExpr { op, operand } => {
+                self.expr(operand);
+                match op {
+                    UnaryOperator::Not => Some(Type::Bool),
+                    UnaryOperator::Negate => Some(Type::Number),
+                }
+            }
+            NodeKind::Function(function) => {
+                self.define(&function.name, Binding::Function(function));
+                self.function(function);
+                None
+            }
+            NodeKind::Closure { function, .. } => {
+                self.function(function);
+                None
+            }
+            NodeKind::Call { callee, args } => self.call(callee, args),
+            NodeKind::Return(value) => {
+                let t = match value {
+                    Some(value) => self.expr(value),
+                    None => Some(Type::Nil),
+                };
+                match self.returns.last().cloned() {
+                    Some(Some(expected)) => {
+                        self.expect(&expected, &t, node.span, "the return value")
+                    }
+                    Some(None) => {}
+                    None => self.error(node.span, "`return` outside of a function".to_string()),
+                }
+                None
+            }
+            NodeKind::Struct(s) => {
+                let this = self.this.replace(&s.name);
+                let mut visible: Vec<&'a Field> = s.fields.iter().collect();
+                visible.extend(self.implemented_fields(&s.name));
+                self.members(&s.fields, &s.methods, visible);
+                self.this = this;
+                None
+            }
+            NodeKind::Shared(s) => {
+                // `this` is whichever struct implements the interface
+                let this = self.this.take();
+                self.members(&s.fields, &s.methods, s.fields.iter().collect());
+                self.this = this;
+                None
+            }
+            NodeKind::Impl(i) => {
+                for field in i.overrides.iter() {
+                    let t = self.expr(&field.value);
+                    let declared = self
+                        .shared
+                        .get(i.shared.as_str())
+                        .and_then(|s| s.fields.iter().find(|f| f.name == field.name));
+                    if let Some(declared) = declared {
+                        self.expect_field(declared, &t, field.value.span);
+                    }
+                }
+                let this = self.this.replace(&i.target);
+                let mut visible: Vec<&'a Field> = match self.structs.get(i.target.as_str()) {
+                    Some(s) => s.fields.iter().collect(),
+                    None => vec![],
+                };
+                visible.extend(self.implemented_fields(&i.target));
+                self.methods(&i.methods, visible);
+                self.this = this;
+                None
+            }
+            NodeKind::Enum(e) => {
+                for variant in e.variants.iter() {
+                    match &variant.kind {
+                        VariantKind::Unit => {}
+                        VariantKind::Tuple(types) => {
+                            for t in types.iter().flatten() {
+                                self.check_annotation(t, variant.span);
+                            }
+                        }
+                        VariantKind::Struct(fields) => {
+                            for field in fields.iter() {
+                                if let Some(t) = &field.annotation {
+                                    self.check_annotation(t, field.span);
+                                }
+                            }
+                        }
+                    }
+                }
+                None
+            }
+            NodeKind::StructLiteral {
+                name,
+                variant,
+                fields,
+            } => {
+                for (field, value) in fields.iter() {
+                    let t = self.expr(value);
+                    match variant {
+                        Some(variant) => {
+                            let declared = self
+                                .variant_fields(name, variant)
+                                .and_then(|fields| fields.iter().find(|f| f.name == *field))
+                                .and_then(|f| f.annotation.as_ref());
+                            if let Some(declared) = declared {
+                                self.expect(declared, &t, value.span, &format!("field '{field}'"));
+                            }
+                        }
+                        None => {
+                            if let Some(declared) = self.field(name, field) {
+                                self.expect_field(declared, &t, value.span);
+                            }
+                        }
+                    }
+                }
+                if variant.is_none() {
+                    for missing in self.required_fields(name) {
+                        if fields.iter().all(|(field, _)| field != missing) {
+                            let e = CompilerError::at(
+                                node.span,
+                                format!("Missing field '{missing}' in '{name}' literal"),
+                                ErrorTypes::SemanticError,
+                            )
+                            .with_help(&format!(
+                                "give it a value, or declare it optional with `{missing}?`"
+                            ));
+                            self.diagnostics.push(e);
+                        }
+                    }
+                }
+                Some(Type::Named(name.clone()))
+            }
+            NodeKind::Path { base, member } => {
+                let unit = self.enums.get(base.as_str()).is_some_and(|e| {
+                    e.variants
+                        .iter()
+                        .any(|v| v.name == *member && matches!(v.kind, VariantKind::Unit))
+                });
+                unit.then(|| Type::Named(base.clone()))
+            }
+            NodeKind::Get { object, field, .. } => {
+                // an optional field may hold `nil`
+                let (t, optional) = self.get(object, field)?;
+                (!optional).then_some(t)
+            }
+            NodeKind::Block(block) => self.block(block),
+            NodeKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expr(condition);
+                let then = self.block(then);
+                let otherwise = otherwise.as_ref().map(|o| self.expr(o))?;
+                then.filter(|t| Some(t) == otherwise.as_ref())
+            }
+            NodeKind::While { condition, body } => {
+                self.expr(condition);
+                self.block(body);
+                None
+            }
+            NodeKind::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                let is_range = matches!(
+                    iterable.kind,
+                    NodeKind::BinaryExpr {
+                        op: Operator::Range,
+                        ..
+                    }
+                );
+                self.expr(iterable);
+                self.scopes.push(HashMap::new());
+                let t = is_range.then_some(Type::Number);
+                self.define(binding, Binding::Variable(t));
+                self.block(body);
+                self.scopes.pop();
+                None
+            }
+            NodeKind::Match { scrutinee, arms } => {
+                self.expr(scrutinee);
+                let types: Vec<Option<Type>> = arms.iter().map(|arm| self.arm(arm)).collect();
+                match types.split_first() {
+                    Some((first, rest)) if rest.iter().all(|t| t == first) => first.clone(),
+                    _ => None,
+                }
+            }
+            NodeKind::Array(items) | NodeKind::Tuple(items) => {
+                for item in items.iter() {
+                    self.expr(item);
+                }
+                None
+            }
+            NodeKind::Map(entries) => {
+                for (key, value) in entries.iter() {
+                    self.expr(key);
+                    self.expr(value);
+                }
+                None
+            }
+            NodeKind::Index { object, index } => {
+                self.expr(object);
+                self.expr(index);
+                None
+            }
+            NodeKind::Break | NodeKind::Continue => None,
+        }
+    }
+
+    /// Reports assignments to anything but a `let mut` variable and values
+    /// that do not fit the variable's type. Names not declared in the
+    /// program, like host globals, are left to the interpreter.
+    fn assign(
+        &mut self,
+        name: &str,
+        op: &Option<Operator>,
+        found: &Option<Type>,
+        span: Span,
+        value: Span,
+    ) {
+        let t = match self.lookup(name) {
+            Some(Binding::Mutable(t)) => t,
+            Some(Binding::Variable(_)) => {
+                let e = CompilerError::at(
+                    span,
+                    format!("Cannot assign twice to immutable variable '{name}'"),
+                    ErrorTypes::SemanticError,
+                )
+                .with_help("only variables declared with `let mut` can be assigned");
+                self.diagnostics.push(e);
+                return;
+            }
+            Some(Binding::Function(_)) => {
+                self.error(span, format!("Cannot assign to function '{name}'"));
+                return;
+            }
+            None => return,
+        };
+
+        let Some(expected) = t.and_then(|t| self.resolve(&t)) else {
+            return;
+        };
+        let found = match op {
+            Some(op) => operator_type(op, Some(expected.clone()), found.clone()),
+            None => found.clone(),
+        };
+        self.expect(&expected, &found, value, &format!("'{name}'"));
+    }
+
+    fn call(&mut self, callee: &'a Node, args: &'a [Node]) -> Option<Type> {
+        let types: Vec<Option<Type>> = args.iter().map(|arg| self.expr(arg)).collect();
+
+        let (function, this) = match &callee.kind {
+            NodeKind::Name(name) => match self.lookup(name) {
+                Some(Binding::Function(function)) => (function, None),
+                _ => return None,
+            },
+            NodeKind::Get { object, field, .. } => {
+                let Some(Type::Named(name)) = self.expr(object) else {
+                    return None;
+                };
+                let name = self.structs.get_key_value(name.as_str())?.0;
+                (self.method(name, field)?, Some(*name))
+            }
+            NodeKind::Path { base, member } => {
+                if let Some(e) = self.enums.get(base.as_str()) {
+                    return self.construct(e, member, args, &types);
+                }
+                let (name, s) = self.structs.get_key_value(base.as_str())?;
+                let method = s.methods.iter().find(|m| m.name == *member)?;
+                (method.as_ref(), Some(*name))
+            }
+            _ => {
+                self.expr(callee);
+                return None;
+            }
+        };
+
+        for ((param, arg), t) in function.params.iter().zip(args.iter()).zip(types.iter()) {
+            if let Some(annotation) = &param.annotation {
+                if let Some(expected) = self.resolve_in(annotation, this) {
+                    self.expect(
+                        &expected,
+                        t,
+                        arg.span,
+                        &format!("parameter '{}'", param.name),
+                    );
+                }
+            }
+        }
+        self.resolve_in(function.return_type.as_ref()?, this)
+    }
+
+    /// `Enum::Variant(args)`, checked against the variant's types.
+    fn construct(
+        &mut self,
+        declaration: &EnumDecl,
+        variant: &str,
+        args: &[Node],
+        types: &[Option<Type>],
+    ) -> Option<Type> {
+        let variant = declaration.variants.iter().find(|v| v.name == variant)?;
+        if let VariantKind::Tuple(declared) = &variant.kind {
+            for ((expected, arg), t) in declared.iter().zip(args.iter()).zip(types.iter()) {
+                if let Some(expected) = expected {
+                    self.expect(expected, t, arg.span, &format!("'{}'", variant.name));
+                }
+            }
+        }
+        Some(Type::Named(declaration.name.clone()))
+    }
+
+    /// Checks a function body against its parameters and return type.
+    fn function(&mut self, function: &'a Function) {
+        let Some(body) = &function.body else {
+            return;
+        };
+
+        self.scopes.push(HashMap::new());
+        for param in function.params.iter() {
+            if let Some(annotation) = &param.annotation {
+                self.check_annotation(annotation, param.span);
+            }
+            self.define(&param.name, Binding::Variable(param.annotation.clone()));
+        }
+
+        let returns = function.return_type.as_ref().and_then(|t| self.resolve(t));
+        if let Some(t) = &function.return_type {
+            self.check_annotation(t, function.span);
+        }
+        self.returns.push(returns.clone());
+
+        let value = self.block(body);
+        if let (Some(expected), Some(node)) = (&returns, &body.value) {
+            self.expect(expected, &value, node.span, "the return value");
+        }
+
+        self.returns.pop();
+        self.scopes.pop();
+    }
+
+    /// Checks the fields of a struct or interface, then its methods, which
+    /// see the `visible` fields of their instance by name.
+    fn members(
+        &mut self,
+        fields: &'a [Field],
+        methods: &'a [Rc<Function>],
+        visible: Vec<&'a Field>,
+    ) {
+        for field in fields.iter() {
+            if let Some(annotation) = &field.annotation {
+                self.check_annotation(annotation, field.span);
+            }
+            if let Some(default) = &field.default {
+                let t = self.expr(default);
+                self.expect_field(field, &t, default.span);
+            }
+        }
+        self.methods(methods, visible);
+    }
+
+    /// Checks methods in a scope binding each field of the instance. Fields
+    /// can be assigned, and an optional one may hold `nil`, so its type is
+    /// left dynamic.
+    fn methods(&mut self, methods: &'a [Rc<Function>], fields: Vec<&'a Field>) {
+        let mut scope = HashMap::new();
+        for field in fields {
+            let t = match field.optional {
+                true => None,
+                false => field.annotation.as_ref().and_then(|t| self.resolve(t)),
+            };
+            scope.insert(field.name.as_str(), Binding::Mutable(t));
+        }
+        self.scopes.push(scope);
+        for method in methods.iter() {
+            self.function(method);
+        }
+        self.scopes.pop();
+    }
+
+    /// Fields the interfaces implemented on the struct `name` add to it.
+    fn implemented_fields(&self, name: &str) -> Vec<&'a Field> {
+        self.impls
+            .iter()
+            .filter(|i| i.target == name)
+            .filter_map(|i| self.shared.get(i.shared.as_str()))
+            .flat_map(|s| s.fields.iter())
+            .collect()
+    }
+
+    fn block(&mut self, block: &'a Block) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        for node in block.body.iter() {
+            self.expr(node);
+        }
+        let t = block.value.as_ref().and_then(|value| self.expr(value));
+        self.scopes.pop();
+        t
+    }
+
+    fn arm(&mut self, arm: &'a MatchArm) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        let mut bindings = vec![&arm.pattern];
+        while let Some(pattern) = bindings.pop() {
+            match &pattern.kind {
+                PatternKind::Binding(name) => self.define(name, Binding::Variable(None)),
+                PatternKind::Struct { fields, .. }
+                | PatternKind::Variant {
+                    fields: VariantPattern::Struct(fields),
+                    ..
+                } => bindings.extend(fields.iter().map(|(_, p)| p)),
+                PatternKind::Variant {
+                    fields: VariantPattern::Tuple(patterns),
+                    ..
+                } => bindings.extend(patterns.iter()),
+                _ => {}
+            }
+        }
+
+        if let Some(guard) = &arm.guard {
+            self.expr(guard);
+        }
+        let t = self.expr(&arm.body);
+        self.scopes.pop();
+        t
+    }
+
+    /// The declared type of `object.field` and whether the field is optional.
+    fn get(&mut self, object: &'a Node, field: &str) -> Option<(Type, bool)> {
+        let Some(Type::Named(name)) = self.expr(object) else {
+            return None;
+        };
+        let field = self.field(&name, field)?;
+        let t = self.resolve_in(field.annotation.as_ref()?, Some(&name))?;
+        Some((t, field.optional))
+    }
+
+    /// Fields of a struct that a literal has to give, those that are neither
+    /// optional nor have a default, own or from an impl.
+    fn required_fields(&self, name: &str) -> Vec<&'a str> {
+        let own = self.structs.get(name).map(|s| s.fields.iter());
+        let mut required: Vec<&'a str> = own
+            .into_iter()
+            .flatten()
+            .filter(|f| !f.optional && f.default.is_none())
+            .map(|f| f.name.as_str())
+            .collect();
+        for i in self.impls.iter().filter(|i| i.target == name) {
+            let Some(shared) = self.shared.get(i.shared.as_str()) else {
+                continue;
+            };
+            let fields = shared.fields.iter().filter(|f| {
+                !f.optional && f.default.is_none() && !i.overrides.iter().any(|o| o.name == f.name)
+            });
+            required.extend(fields.map(|f| f.name.as_str()));
+        }
+        required
+    }
+
+    /// A field of a struct or of an interface implemented on it.
+    fn field(&self, name: &str, field: &str) -> Option<&'a Field> {
+        let own = self
+            .structs
+            .get(name)
+            .and_then(|s| s.fields.iter().find(|f| f.name == field));
+        own.or_else(|| {
+            self.implemented(name)
+                .find_map(|s| s.fields.iter().find(|f| f.name == field))
+        })
+    }
+
+    /// A method of a struct, from its declaration or an impl.
+    fn method(&self, name: &str, method: &str) -> Option<&'a Function> {
+        let own = self
+            .structs
+            .get(name)
+            .and_then(|s| s.methods.iter().find(|m| m.name == method));
+        let implemented = || {
+            self.impls
+                .iter()
+                .filter(|i| i.target == name)
+                .find_map(|i| i.methods.iter().find(|m| m.name == method))
+        };
+        own.or_else(implemented).map(|m| m.as_ref())
+    }
+
+    fn implemented<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'a Rc<SharedDecl>> + 'b {
+        self.impls
+            .iter()
+            .filter(move |i| i.target == name)
+            .filter_map(|i| self.shared.get(i.shared.as_str()).copied())
+    }
+
+    fn variant_fields(&self, name: &str, variant: &str) -> Option<&'a [Param]> {
+        let variant = self
+            .enums
+            .get(name)?
+            .variants
+            .iter()
+            .find(|v| v.name == variant)?;
+        match &variant.kind {
+            VariantKind::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Reports a value whose known type does not fit `expected`.
+    fn expect(&mut self, expected: &Type, found: &Option<Type>, span: Span, what: &str) {
+        let Some(found) = found else {
+            return;
+        };
+        if !self.accepts(expected, found) {
+            self.error(
+                span,
+                format!("Mismatched types: expected {expected} for {what}, found {found}"),
+            );
+        }
+    }
+
+    /// Like `expect`, but optional fields also accept `nil`.
+    fn expect_field(&mut self, field: &Field, found: &Option<Type>, span: Span) {
+        let Some(annotation) = &field.annotation else {
+            return;
+        };
+        if field.optional && *found == Some(Type::Nil) {
+            return;
+        }
+        if let Some(expected) = self.resolve(annotation) {
+            let what = format!("field '{}'", field.name);
+            self.expect(&expected, found, span, &what);
+        }
+    }
+
+    /// Whether a value of type `found` can be used as an `expected`. A struct
+    /// fits every interface implemented on it.
+    fn accepts(&self, expected: &Type, found: &Type) -> bool {
+        if expected == found {
+            return true;
+        }
+        match (expected, found) {
+            (Type::Named(interface), Type::Named(name))
+                if self.shared.contains_key(interface.as_str()) =>
+            {
+                self.impls
+                    .iter()
+                    .any(|i| i.shared == *interface && i.target == *name)
+            }
+            _ => false,
+        }
+    }
+
+    /// Reports annotations naming a type that is not declared.
+    fn check_annotation(&mut self, annotation: &Type, span: Span) {
+        if let Type::Named(name) = annotation {
+            let known = self.structs.contains_key(name.as_str())
+                || self.shared.contains_key(name.as_str())
+                || self.enums.contains_key(name.as_str());
+            if !known {
+                self.error(span, format!("Undefined type '{name}'"));
+            }
+        }
+    }
+
+    fn resolve(&self, annotation: &Type) -> Option<Type> {
+        self.resolve_in(annotation, self.this)
+    }
+
+    /// The type an annotation stands for, `this` being the struct `this`.
+    /// Undefined types are dynamic, they are reported once where declared.
+    fn resolve_in(&self, annotation: &Type, this: Option<&str>) -> Option<Type> {
+        match annotation {
+            Type::This => this.map(|s| Type::Named(s.to_string())),
+            Type::Named(name)
+                if !self.structs.contains_key(name.as_str())
+                    && !self.shared.contains_key(name.as_str())
+                    && !self.enums.contains_key(name.as_str()) =>
+            {
+                None
+            }
+            t => Some(t.clone()),
+        }
+    }
+
+    fn define(&mut self, name: &'a str, binding: Binding<'a>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, binding);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Binding<'a>> {
+        self.scopes.iter().rev().find_map(|s| s.get(name).cloned())
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.diagnostics
+            .push(CompilerError::at(span, message, ErrorTypes::SemanticError));
+    }
+}
+
+/// Type of an operator's result. Operands of the wrong type are left to the
+/// interpreter, only annotations are enforced here.
+fn operator_type(op: &Operator, l: Option<Type>, r: Option<Type>) -> Option<Type> {
+    match op {
+        Operator::Add => match (l, r) {
+            (Some(Type::String), _) | (_, Some(Type::String)) => Some(Type::String),
+            (Some(Type::Number), Some(Type::Number)) => Some(Type::Number),
+            _ => None,
+        },
+        // these only succeed on numbers
+        Operator::Sub | Operator::Mult | Operator::Div | Operator::BitOr => Some(Type::Number),
+        Operator::Range => None,
+        Operator::Coalesce => match l {
+            Some(Type::Nil) => r,
+            l => l,
+        },
+        Operator::Equal
+        | Operator::NotEqual
+        | Operator::Greater
+        | Operator::GreaterEqual
+        | Operator::Less
+        | Operator::LessEqual
+        | Operator::And
+        | Operator::Or => Some(Type::Bool),
+    }
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal {
+        Literal::Float(_) | Literal::BigFloat(_) => Type::Number,
+        Literal::String(_) => Type::String,
+        Literal::Char(_) => Type::Char,
+        Literal::Boolean(_) => Type::Bool,
+        Literal::Nil => Type::Nil,
+    }
+}
+
+/// Runs the type checker over a parsed program.
+pub fn check_types(
+    program: &Program,
+    declarations: &Declarations,
+) -> Result<(), Vec<CompilerError>> {
+    TypeChecker::new(program, declarations).check(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::scanner::Scanner;
+    use parser::parser::Parser;
+
+    use super::check_types;
+    use crate::declarations::Declarations;
+
+    /// Messages of every type error in `source`.
+    fn errors(source: &str) -> Vec<String> {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+        let program = Parser::new(&scanner.tokens).parse_program().unwrap();
+        match check_types(&program, &Declarations::new()) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|e| e.message.clone()).collect(),
+        }
+    }
+
+    #[test]
+    fn unannotated_code_is_never_rejected() {
+        assert!(errors("let a = 1; fn f(x) { x + \"s\" } f(nil); let b = a + f;").is_empty());
+    }
+
+    #[test]
+    fn declarations_are_held_to_their_annotation() {
+        assert_eq!(
+            errors("let a: number = \"x\"; let b: string = \"y\";"),
+            ["Mismatched types: expected number for 'a', found string"]
+        );
+    }
+
+    #[test]
+    fn calls_and_returns_are_checked() {
+        assert_eq!(
+            errors("fn f(n: number) => string { n } f(\"s\");"),
+            [
+                "Mismatched types: expected string for the return value, found number",
+                "Mismatched types: expected number for parameter 'n', found string",
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_are_checked_in_literals_and_methods() {
+        assert_eq!(
+            errors("struct S { n: number; fn set() { n = \"x\"; } } S { n: true };"),
+            [
+                "Mismatched types: expected number for 'n', found string",
+                "Mismatched types: expected number for field 'n', found bool",
+            ]
+        );
+        assert_eq!(
+            errors("struct S { a: number; b?: number; } S {};"),
+            ["Missing field 'a' in 'S' literal"]
+        );
+    }
+
+    #[test]
+    fn fields_shadow_globals_in_methods() {
+        let source = "let n: string = \"a\"; struct S { n: number; fn f() => number { n } }";
+        assert!(errors(source).is_empty());
+    }
+
+    #[test]
+    fn fields_of_implemented_interfaces_are_in_scope() {
+        let source = "struct U { k: number; } shared K { j: number = 1; fn h() => number; } \
+                      impl shared K on U { fn h() => number { k + j } }";
+        assert!(errors(source).is_empty());
+    }
+
+    #[test]
+    fn return_needs_a_function() {
+        assert_eq!(errors("return 1;"), ["`return` outside of a function"]);
+        assert!(errors("fn f() { let g = || { return 1; }; return g(); }").is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use analyzer::declarations::Declarations;
+use error::types::{CompilerError, ErrorTypes};
+use interpreter::{
+    convert::{FromValue, IntoArgs, IntoValue},
+    interpreter::{Interpreter, Unwind},
+    value::Value,
+};
+use parser::types::Program;
+use std::panic::Location;
+
+/// Ion embedded in a Rust application. Globals, declarations and registered
+/// functions persist across every source run on the same engine.
+///
+/// ```no_run
+/// # use interpreter::convert::FromValue;
+/// let mut engine = ion::Engine::new();
+/// engine.register("twice", Some(1), |args| Ok(f64::from_value(args[0].clone())? * 2.0));
+/// engine.run("fn add(a, b) { return twice(a) + b; }").unwrap();
+/// let sum: f64 = engine.call("add", (1, 2)).unwrap();
+/// ```
+#[derive(Default)]
+pub struct Engine {
+    interpreter: Interpreter,
+    // items of the programs run so far, for checking the ones after them
+    declarations: Declarations,
+}
+
+impl Engine {
+    /// An engine with the standard prelude installed.
+    pub fn new() -> Engine {
+        Engine::default()
+    }
+
+    /// Sets how much stack the thread running the engine has, Ion calls recurse
+    /// on it. Without this the engine assumes the 2 MiB of a spawned thread and
+    /// reports deeper recursion as a stack overflow.
+    pub fn set_stack_size(&mut self, bytes: usize) {
+        self.interpreter.set_stack_size(bytes);
+    }
+
+    /// Lexes, parses and checks `source` without running it. Warnings are
+    /// not reported.
+    pub fn compile(&self, source: &str) -> Result<Program, Vec<CompilerError>> {
+        let program = crate::parse(source)?;
+        self.check(&program)?;
+        Ok(program)
+    }
+
+    /// Runs the static passes over `program`, which sees the items declared
+    /// by the programs this engine ran before. Succeeds with the warnings
+    /// found, or fails with every diagnostic.
+    pub fn check(&self, program: &Program) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+        crate::check_after(program, &self.declarations)
+    }
+
+    /// Compiles and runs `source`, returning the value of its last statement.
+    pub fn run(&mut self, source: &str) -> Result<Value, Vec<CompilerError>> {
+        let program = self.compile(source)?;
+        self.execute(&program).map_err(|e| vec![e])
+    }
+
+    /// Runs an already compiled program.
+    pub fn execute(&mut self, program: &Program) -> Result<Value, CompilerError> {
+        self.declarations.extend(program);
+        self.interpreter.interpret(program)
+    }
+
+    /// Exposes a Rust closure to Ion as the global function `name`. `arity`
+    /// of `None` accepts any number of arguments. An error returned by the
+    /// closure becomes a runtime error at the call site.
+    pub fn register<F, R>(&mut self, name: &str, arity: Option<usize>, func: F)
+    where
+        F: Fn(Vec<Value>) -> Result<R, CompilerError> + 'static,
+        R: IntoValue,
+    {
+        self.interpreter
+            .register(name, arity, move |_, args, span| {
+                func(args)
+                    .map(IntoValue::into_value)
+                    .map_err(|e| match e.span {
+                        Some(_) => Unwind::Error(e),
+                        None => Unwind::Error(e.with_span(span)),
+                    })
+            });
+    }
+
+    pub fn set_global(&mut self, name: &str, value: impl IntoValue) {
+        self.interpreter.define_global(name, value.into_value());
+    }
+
+    /// Reads the global `name` as a `T`. Fails when it is not defined or has
+    /// another type.
+    #[track_caller]
+    pub fn get_global<T: FromValue>(&self, name: &str) -> Result<T, CompilerError> {
+        match self.interpreter.global(name) {
+            Some(value) => T::from_value(value),
+            None => Err(undefined(name)),
+        }
+    }
+
+    /// Calls the global function `name` with `args` and converts its result,
+    /// `engine.call::<f64>("add", (1, 2))`.
+    #[track_caller]
+    pub fn call<T: FromValue>(
+        &mut self,
+        name: &str,
+        args: impl IntoArgs,
+    ) -> Result<T, CompilerError> {
+        let Some(callee) = self.interpreter.global(name) else {
+            return Err(undefined(name));
+        };
+        let result = self.interpreter.invoke(callee, args.into_args())?;
+        T::from_value(result)
+    }
+}
+
+/// The global was asked for by the host, so the error points at the Rust
+/// code that asked rather than at any Ion source.
+#[track_caller]
+fn undefined(name: &str) -> CompilerError {
+    let caller = Location::caller();
+    CompilerError::new(
+        caller.line() as usize,
+        caller.column() as usize,
+        format!("Undefined global '{name}'"),
+        ErrorTypes::RuntimeError,
+        Some(caller.file().to_string()),
+    )
+}
@@ -1,19 +1,12 @@
+pub mod render;
 pub mod types;
 
-use std::process::exit;
-use types::{CompilerError};
+use types::CompilerError;
 
 pub fn error(error: &CompilerError) {
     report(error);
 }
 
 fn report(error: &CompilerError) {
-    println!("[LINE {}, COL: {}] {:?}: {}",
-             error.line,
-             error.col,
-             error.error_type,
-             error.message
-             // error.location,
-    );
-    exit(65);
+    eprintln!("{error}");
 }
@@ -0,0 +1,287 @@
+use crate::types::{CompilerError, Frame, Severity};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders errors against the source they came from, rustc style:
+///
+/// ```text
+/// error[E0002]: Unexpected token: ;
+///  --> main.ion:2:14
+///   |
+/// 2 | let b = (1 + ;
+///   |              ^
+/// ```
+pub struct Renderer<'a> {
+    source: &'a str,
+    file_name: &'a str,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source: &'a str, file_name: &'a str) -> Renderer<'a> {
+        Renderer {
+            source,
+            file_name,
+            color: true,
+        }
+    }
+
+    /// Toggles ANSI colors, disable for CI logs or when output is not a terminal.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, error: &CompilerError) -> String {
+        let (label, accent) = match error.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+        let mut out = format!(
+            "{}{label}[{}]{}: {}{}{}\n",
+            self.paint(accent),
+            error.error_type.code(),
+            self.paint(RESET),
+            self.paint(BOLD),
+            error.summary(),
+            self.paint(RESET),
+        );
+
+        let file_name = match error.location.is_empty() {
+            true => self.file_name,
+            false => error.location.as_str(),
+        };
+
+        match error.span {
+            Some(span) => {
+                let (line, col, text) = self.line_at(span.start);
+                let gutter = " ".repeat(line.to_string().len());
+
+                // underline the span, clamped to the end of its first line
+                let width = self.source
+                    [span.start.min(self.source.len())..span.end.min(self.source.len())]
+                    .chars()
+                    .take_while(|c| *c != '\n')
+                    .count()
+                    .max(1);
+                let padding: String = text
+                    .chars()
+                    .take(col - 1)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+
+                out.push_str(&format!(
+                    "{gutter}{}-->{} {file_name}:{line}:{col}\n",
+                    self.paint(BLUE),
+                    self.paint(RESET)
+                ));
+                out.push_str(&format!(
+                    "{gutter} {}|{}\n",
+                    self.paint(BLUE),
+                    self.paint(RESET)
+                ));
+                out.push_str(&format!(
+                    "{}{line} |{} {text}\n",
+                    self.paint(BLUE),
+                    self.paint(RESET)
+                ));
+                out.push_str(&format!(
+                    "{gutter} {}|{} {padding}{}{}{}\n",
+                    self.paint(BLUE),
+                    self.paint(RESET),
+                    self.paint(accent),
+                    "^".repeat(width),
+                    self.paint(RESET)
+                ));
+                if let Some(help) = &error.help {
+                    out.push_str(&format!(
+                        "{gutter} {}={} {}help{}: {help}\n",
+                        self.paint(BLUE),
+                        self.paint(RESET),
+                        self.paint(BOLD),
+                        self.paint(RESET)
+                    ));
+                }
+                // deep recursion repeats the same call, print it once
+                let mut frames: Vec<(&Frame, usize)> = vec![];
+                for frame in error.stack.iter() {
+                    match frames.last_mut() {
+                        Some((last, count)) if *last == frame => *count += 1,
+                        _ => frames.push((frame, 1)),
+                    }
+                }
+                for (frame, count) in frames {
+                    let function = match frame.function.is_empty() {
+                        true => "a closure".to_string(),
+                        false => format!("'{}'", frame.function),
+                    };
+                    // calls made by the host have no place in the source
+                    let place = match frame.span == Default::default() {
+                        true => "by the host".to_string(),
+                        false => format!("at {file_name}:{}:{}", frame.span.line, frame.span.col),
+                    };
+                    let repeated = match count {
+                        1 => String::new(),
+                        n => format!(" ({n} times)"),
+                    };
+                    out.push_str(&format!(
+                        "{gutter} {}={} {}note{}: in {function}, called {place}{repeated}\n",
+                        self.paint(BLUE),
+                        self.paint(RESET),
+                        self.paint(BOLD),
+                        self.paint(RESET)
+                    ));
+                }
+            }
+            None => {
+                if error.line > 0 {
+                    out.push_str(&format!(
+                        " {}-->{} {file_name}:{}:{}\n",
+                        self.paint(BLUE),
+                        self.paint(RESET),
+                        error.line,
+                        error.col
+                    ));
+                }
+                if let Some(help) = &error.help {
+                    out.push_str(&format!(
+                        " {}={} {}help{}: {help}\n",
+                        self.paint(BLUE),
+                        self.paint(RESET),
+                        self.paint(BOLD),
+                        self.paint(RESET)
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Line number, column (both 1 based) and text of the line holding `offset`.
+    fn line_at(&self, offset: usize) -> (usize, usize, &'a str) {
+        let offset = offset.min(self.source.len());
+        let start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |i| offset + i);
+        let line = self.source[..start].matches('\n').count() + 1;
+        let col = self.source[start..offset].chars().count() + 1;
+
+        (line, col, self.source[start..end].trim_end_matches('\r'))
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str {
+        match self.color {
+            true => code,
+            false => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Renderer;
+    use crate::types::{CompilerError, ErrorTypes, Span};
+
+    const SOURCE: &str = "let a = 1;\nlet b = (1 + ;\n";
+
+    fn render(error: &CompilerError) -> String {
+        Renderer::new(SOURCE, "main.ion")
+            .with_color(false)
+            .render(error)
+    }
+
+    fn parse_error(span: Span) -> CompilerError {
+        CompilerError::at(
+            span,
+            "Unexpected token".to_string(),
+            ErrorTypes::ParsingError,
+        )
+    }
+
+    #[test]
+    fn underlines_the_span() {
+        assert_eq!(
+            render(&parse_error(Span::new(24, 25, 2, 14))),
+            "error[E0002]: Unexpected token\n \
+             --> main.ion:2:14\n  \
+             |\n\
+             2 | let b = (1 + ;\n  \
+             |              ^\n"
+        );
+    }
+
+    #[test]
+    fn underlines_the_whole_span_and_adds_help() {
+        let error = parse_error(Span::new(0, 9, 1, 1)).with_help("statements end with a `;`");
+        assert_eq!(
+            render(&error),
+            "error[E0002]: Unexpected token\n \
+             --> main.ion:1:1\n  \
+             |\n\
+             1 | let a = 1;\n  \
+             | ^^^^^^^^^\n  \
+             = help: statements end with a `;`\n"
+        );
+    }
+
+    #[test]
+    fn warnings_are_labelled() {
+        let error = parse_error(Span::new(4, 5, 1, 5)).as_warning();
+        assert!(render(&error).starts_with("warning[E0002]: Unexpected token\n"));
+        let colored = Renderer::new(SOURCE, "main.ion").render(&error);
+        assert!(colored.starts_with("\x1b[1;33mwarning"));
+    }
+
+    #[test]
+    fn spans_are_clamped_to_their_first_line() {
+        let text = render(&parse_error(Span::new(4, 20, 1, 5)));
+        assert!(text.ends_with("1 | let a = 1;\n  |     ^^^^^^\n"));
+    }
+
+    #[test]
+    fn errors_without_a_span_print_their_position() {
+        let error = CompilerError::new(
+            3,
+            7,
+            "Bad".to_string(),
+            ErrorTypes::RuntimeError,
+            Some("lib.ion".to_string()),
+        );
+        assert_eq!(render(&error), "error[E0004]: Bad\n --> lib.ion:3:7\n");
+    }
+
+    #[test]
+    fn colors_are_optional() {
+        let error = parse_error(Span::new(0, 3, 1, 1));
+        let colored = Renderer::new(SOURCE, "main.ion").render(&error);
+        assert!(colored.starts_with("\x1b[1;31merror[E0002]"));
+        assert!(!render(&error).contains('\x1b'));
+    }
+
+    #[test]
+    fn repeated_frames_are_folded() {
+        let error = CompilerError::at(
+            Span::new(0, 3, 1, 1),
+            "Too deep".to_string(),
+            ErrorTypes::RuntimeError,
+        )
+        .with_frame("f", Span::new(4, 5, 1, 5))
+        .with_frame("f", Span::new(4, 5, 1, 5))
+        .with_frame("", Default::default());
+        let text = render(&error);
+        assert!(text.contains("note: in 'f', called at main.ion:1:5 (2 times)\n"));
+        assert!(text.contains("note: in a closure, called by the host\n"));
+    }
+
+    #[test]
+    fn panics_are_marked() {
+        let error = CompilerError::at(Span::new(0, 3, 1, 1), "boom".to_string(), ErrorTypes::Panic);
+        assert!(render(&error).starts_with("error[E0006]: Panicked: boom\n"));
+    }
+}
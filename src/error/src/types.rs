@@ -1,42 +1,197 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
 use crate::error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorTypes {
     GenericCompilerError,
     TokenizationError,
+    ParsingError,
     FileIOError,
+    RuntimeError,
+    SemanticError,
+    /// A runtime error raised by the program itself with `panic(message)`.
+    Panic,
+}
+
+impl ErrorTypes {
+    /// Stable code printed in rendered diagnostics, e.g. `error[E0002]`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorTypes::GenericCompilerError => "E0000",
+            ErrorTypes::TokenizationError => "E0001",
+            ErrorTypes::ParsingError => "E0002",
+            ErrorTypes::FileIOError => "E0003",
+            ErrorTypes::RuntimeError => "E0004",
+            ErrorTypes::SemanticError => "E0005",
+            ErrorTypes::Panic => "E0006",
+        }
+    }
+}
+
+/// Warnings are reported but never stop compilation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
 }
 
-pub struct CompilerError {
+/// Byte offset range `start..end` into the source, along with the line and
+/// column where it starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, col: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            col,
+        }
+    }
+
+    /// Span covering everything from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
+/// A function call a runtime error unwound through: the function's name,
+/// empty for closures, and the span of the call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub function: String,
+    pub span: Span,
+}
+
+/// Everything a diagnostic reports. `CompilerError` keeps it behind a box so
+/// results carrying an error stay small, and derefs to it for field access.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
     pub line: usize,
     pub col: usize,
     pub message: String,
     pub location: String,
     pub error_type: ErrorTypes,
+    pub span: Option<Span>,
+    pub help: Option<String>,
+    pub severity: Severity,
+    /// Calls the error unwound through, innermost first.
+    pub stack: Vec<Frame>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompilerError(Box<Diagnostic>);
+
+impl Deref for CompilerError {
+    type Target = Diagnostic;
+
+    fn deref(&self) -> &Diagnostic {
+        &self.0
+    }
+}
+
+impl DerefMut for CompilerError {
+    fn deref_mut(&mut self) -> &mut Diagnostic {
+        &mut self.0
+    }
 }
 
 impl CompilerError {
-    pub fn new(line: usize, col: usize, message: String, error_type: ErrorTypes, location: Option<String>) -> Self {
-        CompilerError {
+    pub fn new(
+        line: usize,
+        col: usize,
+        message: String,
+        error_type: ErrorTypes,
+        location: Option<String>,
+    ) -> Self {
+        CompilerError(Box::new(Diagnostic {
             line,
             col,
             message,
-            location: location.unwrap_or_else(|| String::new()),
+            location: location.unwrap_or_default(),
             error_type,
+            span: None,
+            help: None,
+            severity: Severity::Error,
+            stack: vec![],
+        }))
+    }
+
+    /// Error pointing at a span of source code.
+    pub fn at(span: Span, message: String, error_type: ErrorTypes) -> Self {
+        CompilerError::new(0, 0, message, error_type, None).with_span(span)
+    }
+
+    /// Points the error at `span`, its line and column follow the span.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.line = span.line;
+        self.col = span.col;
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    /// Records a call the error unwound through on its way to the caller.
+    pub fn with_frame(mut self, function: &str, span: Span) -> Self {
+        self.stack.push(Frame {
+            function: function.to_string(),
+            span,
+        });
+        self
+    }
+
+    /// Downgrades the error to a warning.
+    pub fn as_warning(mut self) -> Self {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+
+    /// The message as reported, a panic is marked as one. `message` itself
+    /// stays what the program passed to `panic`.
+    pub fn summary(&self) -> String {
+        match self.error_type {
+            ErrorTypes::Panic => format!("Panicked: {}", self.message),
+            _ => self.message.clone(),
         }
     }
 
-    pub fn throw_new(line: usize, col: usize, message: &str, error_type: ErrorTypes, location: Option<String>) {
-        Self::throw(
-            &Self::new(
-                line,
-                col,
-                message.to_string(),
-                error_type,
-                location,
-            )
-        )
+    /// Prints the error report. Deciding whether to stop is left to the caller.
+    pub fn report(&self) {
+        error(self)
     }
+}
 
-    pub fn throw(&self) { error(self) }
+impl Display for CompilerError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[LINE {}, COL: {}] {:?}: {}",
+            self.line,
+            self.col,
+            self.error_type,
+            self.summary()
+        )
+    }
 }
+
+impl std::error::Error for CompilerError {}
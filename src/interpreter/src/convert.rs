@@ -0,0 +1,204 @@
+use std::{cell::RefCell, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes};
+
+use crate::value::Value;
+
+/// Rust values that can be handed to Ion code.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// Rust values that can be read back out of an Ion value. Fails when the
+/// value has another type, the error has no span as it comes from the host.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, CompilerError>;
+}
+
+/// Arguments of a call from Rust, a tuple of `IntoValue`s or a `Vec<Value>`.
+pub trait IntoArgs {
+    fn into_args(self) -> Vec<Value>;
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Nil
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+macro_rules! number_into_value {
+    ($($t:ty),*) => {
+        $(impl IntoValue for $t {
+            fn into_value(self) -> Value {
+                Value::Number(self as f64)
+            }
+        })*
+    };
+}
+
+number_into_value!(f32, i32, i64, u32, usize);
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl IntoValue for char {
+    fn into_value(self) -> Value {
+        Value::Char(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+/// `None` becomes `nil`.
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Nil, IntoValue::into_value)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        let items = self.into_iter().map(IntoValue::into_value).collect();
+        Value::Array(Rc::new(RefCell::new(items)))
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        Ok(value)
+    }
+}
+
+impl FromValue for () {
+    fn from_value(_: Value) -> Result<Self, CompilerError> {
+        Ok(())
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            v => Err(mismatch("a number", &v)),
+        }
+    }
+}
+
+/// Only numbers without a fractional part convert to integers.
+impl FromValue for i64 {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::Number(n) if n.fract() == 0.0 => Ok(n as i64),
+            v => Err(mismatch("an integer", &v)),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            v => Err(mismatch("a bool", &v)),
+        }
+    }
+}
+
+impl FromValue for char {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::Char(c) => Ok(c),
+            v => Err(mismatch("a char", &v)),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::String(s) => Ok(s),
+            v => Err(mismatch("a string", &v)),
+        }
+    }
+}
+
+/// `nil` becomes `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        match value {
+            Value::Nil => Ok(None),
+            v => T::from_value(v).map(Some),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, CompilerError> {
+        let items = match value {
+            Value::Array(items) => items.borrow().clone(),
+            Value::Tuple(items) => items.as_ref().clone(),
+            v => return Err(mismatch("an array", &v)),
+        };
+        items.into_iter().map(T::from_value).collect()
+    }
+}
+
+impl IntoArgs for Vec<Value> {
+    fn into_args(self) -> Vec<Value> {
+        self
+    }
+}
+
+macro_rules! tuple_into_args {
+    ($($t:ident),*) => {
+        impl<$($t: IntoValue),*> IntoArgs for ($($t,)*) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<Value> {
+                let ($($t,)*) = self;
+                vec![$($t.into_value()),*]
+            }
+        }
+    };
+}
+
+tuple_into_args!();
+tuple_into_args!(A);
+tuple_into_args!(A, B);
+tuple_into_args!(A, B, C);
+tuple_into_args!(A, B, C, D);
+tuple_into_args!(A, B, C, D, E);
+tuple_into_args!(A, B, C, D, E, F);
+
+fn mismatch(expected: &str, found: &Value) -> CompilerError {
+    CompilerError::new(
+        0,
+        0,
+        format!("Expected {expected}, found {}", found.type_name()),
+        ErrorTypes::RuntimeError,
+        None,
+    )
+}
@@ -0,0 +1,183 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
+
+use parser::types::Type;
+
+use crate::value::{Instance, Value};
+
+/// The type a binding is held to when it is assigned.
+#[derive(Debug, Clone)]
+pub enum TypeLock {
+    /// `let x: number = ...`
+    Annotated(Type),
+    /// `let x := ...`, the type name of the initial value.
+    Inferred(String),
+}
+
+impl TypeLock {
+    /// The lock of a new binding: its annotation, or with `:=` the type of
+    /// its initial value.
+    pub fn new(annotation: Option<&Type>, inferred: bool, value: &Value) -> Option<TypeLock> {
+        match annotation {
+            Some(annotation) => Some(TypeLock::Annotated(annotation.clone())),
+            None if inferred => Some(TypeLock::Inferred(value.type_name())),
+            None => None,
+        }
+    }
+
+    pub fn accepts(&self, value: &Value) -> bool {
+        match self {
+            TypeLock::Annotated(t) => value.is_a(t),
+            TypeLock::Inferred(name) => value.type_name() == *name,
+        }
+    }
+}
+
+impl Display for TypeLock {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TypeLock::Annotated(t) => write!(f, "{t}"),
+            TypeLock::Inferred(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// A single scope of bindings. Scopes are chained through `parent` so lookups
+/// fall back to the enclosing scope when a name is not bound locally.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    // bindings declared with `let mut`, everything else is immutable
+    mutable: HashSet<String>,
+    locks: HashMap<String, TypeLock>,
+    // the instance a method was called on, its fields are read and assigned
+    // by their bare names
+    receiver: Option<Rc<RefCell<Instance>>>,
+    parent: Option<Rc<RefCell<Environment>>>,
+}
+
+/// Why an assignment was refused.
+#[derive(Debug)]
+pub enum AssignError {
+    Undefined,
+    Immutable,
+    Mismatch(TypeLock),
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn with_parent(parent: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            parent: Some(parent),
+            ..Environment::default()
+        }
+    }
+
+    /// Scope of a method call on `instance`. Besides `this`, the fields of
+    /// the instance are visible by name, always reading their current value.
+    pub fn with_receiver(
+        parent: Rc<RefCell<Environment>>,
+        instance: Rc<RefCell<Instance>>,
+    ) -> Environment {
+        let mut scope = Environment::with_parent(parent);
+        scope.define("this", Value::Instance(instance.clone()));
+        scope.receiver = Some(instance);
+        scope
+    }
+
+    /// Binds `name` in this scope, shadowing any previous binding. The
+    /// binding is immutable.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.declare(name, value, false, None);
+    }
+
+    /// Binds `name` like `define`, optionally allowing assignments and
+    /// holding them to `lock`.
+    pub fn declare(&mut self, name: &str, value: Value, mutable: bool, lock: Option<TypeLock>) {
+        self.values.insert(name.to_string(), value);
+        match mutable {
+            true => self.mutable.insert(name.to_string()),
+            false => self.mutable.remove(name),
+        };
+        match lock {
+            Some(lock) => self.locks.insert(name.to_string(), lock),
+            None => self.locks.remove(name),
+        };
+    }
+
+    /// Rebinds `name` in the closest scope that binds it.
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), AssignError> {
+        if !self.values.contains_key(name) {
+            if let Some(instance) = &self.receiver {
+                let struct_type = instance.borrow().struct_type.clone();
+                if let Some(t) = struct_type.rejects(name, &value) {
+                    return Err(AssignError::Mismatch(TypeLock::Annotated(t)));
+                }
+                let mut instance = instance.borrow_mut();
+                if let Some((_, field)) = instance.fields.iter_mut().find(|(n, _)| n == name) {
+                    *field = value;
+                    return Ok(());
+                }
+            }
+            return match &self.parent {
+                Some(parent) => parent.borrow_mut().assign(name, value),
+                None => Err(AssignError::Undefined),
+            };
+        }
+
+        if !self.mutable.contains(name) {
+            return Err(AssignError::Immutable);
+        }
+        if let Some(lock) = self.locks.get(name) {
+            if !lock.accepts(&value) {
+                return Err(AssignError::Mismatch(lock.clone()));
+            }
+        }
+        self.values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// Copies every binding visible from this scope into a single detached
+    /// scope, inner bindings shadowing outer ones.
+    pub fn snapshot(&self) -> Environment {
+        let mut snapshot = match &self.parent {
+            Some(parent) => parent.borrow().snapshot(),
+            None => Environment::new(),
+        };
+        if let Some(instance) = &self.receiver {
+            for (name, value) in instance.borrow().fields.iter() {
+                snapshot.define(name, value.clone());
+            }
+        }
+        for (name, value) in self.values.iter() {
+            snapshot.declare(
+                name,
+                value.clone(),
+                self.mutable.contains(name),
+                self.locks.get(name).cloned(),
+            );
+        }
+        snapshot
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        if let Some(v) = self.values.get(name) {
+            return Some(v.clone());
+        }
+        if let Some(v) = self
+            .receiver
+            .as_ref()
+            .and_then(|i| i.borrow().get(name).cloned())
+        {
+            return Some(v);
+        }
+        self.parent.as_ref()?.borrow().get(name)
+    }
+}
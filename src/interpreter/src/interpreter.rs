@@ -0,0 +1,1565 @@
+use std::{cell::RefCell, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes, Span};
+use parser::types::{
+    Block, Declaration, ImplDecl, Literal, MatchArm, Node, NodeKind, Operator, Pattern,
+    PatternKind, Program, StructDecl, Type, UnaryOperator, VariantKind, VariantPattern,
+};
+
+use crate::{
+    environment::{AssignError, Environment, TypeLock},
+    native::Caller,
+    prelude,
+    value::{
+        EnumType, FieldType, FunctionValue, Instance, SharedType, StructType, Value, VariantData,
+        VariantValue,
+    },
+};
+
+/// Deepest call chain before reporting a stack overflow.
+pub const MAX_CALL_DEPTH: usize = 500;
+
+/// Stack the interpreter assumes its thread has unless told otherwise, what
+/// Rust gives a spawned thread.
+pub const DEFAULT_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// Tree-walking interpreter over a parsed `Program`.
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    env: Rc<RefCell<Environment>>,
+    frames: Vec<CallFrame>,
+    stack_size: usize,
+    /// Address near the top of the host stack when the outermost evaluation
+    /// started, to measure how much of it calls use.
+    stack_base: usize,
+}
+
+/// An active function call, used to bound recursion depth.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub name: String,
+    pub span: Span,
+}
+
+/// Reasons evaluation stops early. `return` unwinds to the enclosing call and
+/// `break`/`continue` to the enclosing loop, the same way an error unwinds to
+/// the caller of `interpret`.
+#[derive(Debug)]
+pub enum Unwind {
+    Return(Value),
+    Break(Span),
+    Continue(Span),
+    Error(CompilerError),
+}
+
+impl Unwind {
+    /// Turns a `break` or `continue` that escaped every loop into an error.
+    fn outside_loop(self) -> Unwind {
+        match self {
+            Unwind::Break(span) => {
+                runtime_error(span, "`break` outside of a loop".to_string()).into()
+            }
+            Unwind::Continue(span) => {
+                runtime_error(span, "`continue` outside of a loop".to_string()).into()
+            }
+            u => u,
+        }
+    }
+}
+
+impl From<CompilerError> for Unwind {
+    fn from(error: CompilerError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Caller for Interpreter {
+    fn call(&mut self, callee: Value, args: Vec<Value>, span: Span) -> Result<Value, Unwind> {
+        Interpreter::call(self, callee, args, span)
+    }
+}
+
+impl Interpreter {
+    /// An interpreter with the prelude of native functions installed.
+    pub fn new() -> Interpreter {
+        let mut globals = Environment::new();
+        prelude::install(&mut globals);
+        let globals = Rc::new(RefCell::new(globals));
+        Interpreter {
+            env: globals.clone(),
+            globals,
+            frames: vec![],
+            stack_size: DEFAULT_STACK_SIZE,
+            stack_base: 0,
+        }
+    }
+
+    /// Sets how much stack the thread running the interpreter has. Every Ion
+    /// call recurses on it, so calls fail with a stack overflow once three
+    /// quarters of it are used, or past `MAX_CALL_DEPTH`.
+    pub fn set_stack_size(&mut self, bytes: usize) {
+        self.stack_size = bytes;
+    }
+
+    /// Defines a native function in the global scope. `arity` of `None`
+    /// accepts any number of arguments.
+    pub fn register<F>(&mut self, name: &str, arity: Option<usize>, func: F)
+    where
+        F: Fn(&mut dyn Caller, Vec<Value>, Span) -> Result<Value, Unwind> + 'static,
+    {
+        prelude::register(&mut self.globals.borrow_mut(), name, arity, func);
+    }
+
+    /// Binds `name` in the global scope, shadowing any previous binding.
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(name, value);
+    }
+
+    pub fn global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name)
+    }
+
+    /// Evaluates every top level node in order and returns the value of the
+    /// last one. Top level functions are defined first, so they can be called
+    /// before their declaration.
+    pub fn interpret(&mut self, program: &Program) -> Result<Value, CompilerError> {
+        for node in program.tree.iter() {
+            if let NodeKind::Function(_) = node.kind {
+                self.evaluate(node)?;
+            }
+        }
+        let mut last = Value::Nil;
+        for node in program.tree.iter() {
+            last = match node.kind {
+                NodeKind::Function(_) => Value::Nil,
+                _ => self.evaluate(node)?,
+            };
+        }
+        Ok(last)
+    }
+
+    /// Evaluates a single node. A top level `return` simply yields its value.
+    pub fn evaluate(&mut self, node: &Node) -> Result<Value, CompilerError> {
+        self.enter();
+        finish(self.eval(node))
+    }
+
+    /// Calls `callee` from the host, outside of any Ion code.
+    pub fn invoke(&mut self, callee: Value, args: Vec<Value>) -> Result<Value, CompilerError> {
+        self.enter();
+        finish(self.call(callee, args, Span::default()))
+    }
+
+    /// Marks where the host stack starts when not already inside a call.
+    fn enter(&mut self) {
+        if self.frames.is_empty() {
+            self.stack_base = stack_address();
+        }
+    }
+
+    /// Whether another call could overflow the host stack.
+    fn stack_exhausted(&self) -> bool {
+        self.frames.len() >= MAX_CALL_DEPTH
+            || self.stack_base.abs_diff(stack_address()) > self.stack_size / 4 * 3
+    }
+
+    fn eval(&mut self, node: &Node) -> Result<Value, Unwind> {
+        match &node.kind {
+            NodeKind::Literal(literal) => Ok(literal_value(literal)),
+            NodeKind::Name(name) => match self.env.borrow().get(name) {
+                Some(v) => Ok(v),
+                None => {
+                    Err(runtime_error(node.span, format!("Undefined variable '{name}'")).into())
+                }
+            },
+            NodeKind::Declaration(declaration) => self.declaration(declaration),
+            NodeKind::BinaryExpr { op, lhs, rhs } => self.binary(op, lhs, rhs, node.span),
+            NodeKind::UnaryExpr { op, operand } => {
+                let value = self.eval(operand)?;
+                match (op, value) {
+                    (UnaryOperator::Negate, Value::Number(n)) => Ok(Value::Number(-n)),
+                    (UnaryOperator::Not, v) => Ok(Value::Boolean(!v.is_truthy())),
+                    (UnaryOperator::Negate, v) => Err(runtime_error(
+                        node.span,
+                        format!("Cannot negate a value of type {}", v.type_name()),
+                    )
+                    .into()),
+                }
+            }
+            NodeKind::Function(function) => {
+                let value = Value::Function(Rc::new(FunctionValue {
+                    declaration: function.clone(),
+                    closure: self.env.clone(),
+                }));
+                self.env.borrow_mut().define(&function.name, value);
+                Ok(Value::Nil)
+            }
+            NodeKind::Closure { function, by_value } => {
+                let closure = match by_value {
+                    true => Rc::new(RefCell::new(self.env.borrow().snapshot())),
+                    false => self.env.clone(),
+                };
+                Ok(Value::Function(Rc::new(FunctionValue {
+                    declaration: function.clone(),
+                    closure,
+                })))
+            }
+            NodeKind::Call { callee, args } => {
+                let callee = match &callee.kind {
+                    // `a?.method()` skips the call and its arguments when `a` is nil
+                    NodeKind::Get {
+                        object,
+                        field,
+                        optional: true,
+                    } => match self.eval(object)? {
+                        Value::Nil => return Ok(Value::Nil),
+                        object => get(&self.globals, object, field, callee.span)?,
+                    },
+                    _ => self.eval(callee)?,
+                };
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    values.push(self.eval(arg)?);
+                }
+                self.call(callee, values, node.span)
+            }
+            NodeKind::Return(value) => {
+                let value = match value {
+                    Some(v) => self.eval(v)?,
+                    None => Value::Nil,
+                };
+                Err(Unwind::Return(value))
+            }
+            NodeKind::Struct(declaration) => {
+                let value = Value::Struct(Rc::new(struct_type(&self.env, declaration)));
+                self.env.borrow_mut().define(&declaration.name, value);
+                Ok(Value::Nil)
+            }
+            NodeKind::Shared(declaration) => {
+                let value = Value::Shared(Rc::new(SharedType {
+                    declaration: declaration.clone(),
+                    closure: self.env.clone(),
+                }));
+                self.env.borrow_mut().define(&declaration.name, value);
+                Ok(Value::Nil)
+            }
+            NodeKind::Impl(declaration) => {
+                implement(&self.env, declaration)?;
+                Ok(Value::Nil)
+            }
+            NodeKind::Block(block) => self.scoped_block(block),
+            NodeKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.eval(condition)?.is_truthy() {
+                    self.scoped_block(then)
+                } else if let Some(otherwise) = otherwise {
+                    self.eval(otherwise)
+                } else {
+                    Ok(Value::Nil)
+                }
+            }
+            NodeKind::While { condition, body } => {
+                while self.eval(condition)?.is_truthy() {
+                    match self.scoped_block(body) {
+                        Ok(_) | Err(Unwind::Continue(_)) => {}
+                        Err(Unwind::Break(_)) => break,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(Value::Nil)
+            }
+            NodeKind::For {
+                binding,
+                iterable,
+                body,
+            } => self.for_loop(binding, iterable, body),
+            NodeKind::Match { scrutinee, arms } => self.match_arms(scrutinee, arms, node.span),
+            NodeKind::Enum(declaration) => {
+                let value = Value::Enum(Rc::new(EnumType {
+                    declaration: declaration.clone(),
+                }));
+                self.env.borrow_mut().define(&declaration.name, value);
+                Ok(Value::Nil)
+            }
+            NodeKind::Break => Err(Unwind::Break(node.span)),
+            NodeKind::Continue => Err(Unwind::Continue(node.span)),
+            NodeKind::StructLiteral {
+                name,
+                variant,
+                fields,
+            } => {
+                let mut given = Vec::with_capacity(fields.len());
+                for (field, value) in fields.iter() {
+                    given.push((field.clone(), self.eval(value)?, value.span));
+                }
+                let env = self.env.clone();
+                match variant {
+                    Some(variant) => variant_literal(&env, name, variant, given, node.span),
+                    None => struct_literal(&env, name, given, node.span, &mut |default, scope| {
+                        self.eval_in(default, scope.clone())
+                    }),
+                }
+            }
+            NodeKind::Path { base, member } => path(&self.env, base, member, node.span),
+            NodeKind::Array(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    values.push(self.eval(item)?);
+                }
+                Ok(Value::Array(Rc::new(RefCell::new(values))))
+            }
+            NodeKind::Tuple(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    values.push(self.eval(item)?);
+                }
+                Ok(Value::Tuple(Rc::new(values)))
+            }
+            NodeKind::Map(entries) => {
+                let mut map: Vec<(Value, Value)> = Vec::with_capacity(entries.len());
+                for (key, value) in entries.iter() {
+                    let key = self.eval(key)?;
+                    let value = self.eval(value)?;
+                    // a repeated key keeps the last value
+                    match map.iter_mut().find(|(k, _)| *k == key) {
+                        Some(entry) => entry.1 = value,
+                        None => map.push((key, value)),
+                    }
+                }
+                Ok(Value::Map(Rc::new(RefCell::new(map))))
+            }
+            NodeKind::Index { object, index } => {
+                let object = self.eval(object)?;
+                let i = self.eval(index)?;
+                index_value(&object, &i)
+                    .map_err(|message| runtime_error(index.span, message).into())
+            }
+            NodeKind::Get {
+                object,
+                field,
+                optional,
+            } => match self.eval(object)? {
+                Value::Nil if *optional => Ok(Value::Nil),
+                object => get(&self.globals, object, field, node.span),
+            },
+            NodeKind::Try(value) => {
+                let value = self.eval(value)?;
+                match value.as_result() {
+                    Some(Ok(v)) => Ok(v.clone()),
+                    // an `Err` returns from the current function as is
+                    Some(Err(_)) if !self.frames.is_empty() => Err(Unwind::Return(value)),
+                    Some(Err(e)) => Err(runtime_error(node.span, format!("Unhandled error: {e}"))
+                        .with_help("`?` outside of a function has nowhere to return the error to")
+                        .into()),
+                    None => Err(runtime_error(
+                        node.span,
+                        format!("`?` expects a Result, found {}", value.type_name()),
+                    )
+                    .into()),
+                }
+            }
+            NodeKind::Assign { target, op, value } => self.assign(target, op, value, node.span),
+            NodeKind::This => match self.env.borrow().get("this") {
+                Some(v) => Ok(v),
+                None => Err(runtime_error(
+                    node.span,
+                    "`this` can only be used inside a method".to_string(),
+                )
+                .into()),
+            },
+        }
+    }
+
+    /// Calls a function value with already evaluated arguments in a fresh
+    /// frame whose scope is a child of the function's closure.
+    pub fn call(&mut self, callee: Value, args: Vec<Value>, span: Span) -> Result<Value, Unwind> {
+        let function = match callee {
+            Value::Function(f) => f,
+            Value::Constructor(enum_type, tag) => return construct(enum_type, tag, args, span),
+            Value::Native(native) => return native.invoke(self, args, span),
+            v => {
+                return Err(runtime_error(
+                    span,
+                    format!("Cannot call a value of type {}", v.type_name()),
+                )
+                .into())
+            }
+        };
+
+        let declaration = &function.declaration;
+        let Some(body) = &declaration.body else {
+            return Err(runtime_error(
+                span,
+                format!("Function '{}' has no body", declaration.name),
+            )
+            .into());
+        };
+        if args.len() != declaration.params.len() {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "Function '{}' expects {} argument(s) but got {}",
+                    function.name(),
+                    declaration.params.len(),
+                    args.len()
+                ),
+            )
+            .into());
+        }
+
+        check_args(&function, &args, span)?;
+        if self.stack_exhausted() {
+            return Err(runtime_error(span, "Stack overflow".to_string()).into());
+        }
+
+        let mut scope = Environment::with_parent(function.closure.clone());
+        for (param, arg) in declaration.params.iter().zip(args) {
+            scope.define(&param.name, arg);
+        }
+
+        self.frames.push(CallFrame {
+            name: function.name().to_string(),
+            span,
+        });
+        let result = self.execute_block(body, Rc::new(RefCell::new(scope)));
+        self.frames.pop();
+
+        let value = match result.map_err(Unwind::outside_loop) {
+            Ok(v) | Err(Unwind::Return(v)) => v,
+            Err(Unwind::Error(e)) => return Err(e.with_frame(&declaration.name, span).into()),
+            Err(e) => return Err(e),
+        };
+        check_return(&function, &value, span)?;
+        Ok(value)
+    }
+
+    /// Runs `for binding in iterable`, each iteration gets a fresh scope.
+    fn for_loop(&mut self, binding: &str, iterable: &Node, body: &Block) -> Result<Value, Unwind> {
+        let items = iterate(&self.eval(iterable)?)
+            .map_err(|message| runtime_error(iterable.span, message))?;
+
+        for item in items {
+            let mut scope = Environment::with_parent(self.env.clone());
+            scope.define(binding, item);
+            match self.execute_block(body, Rc::new(RefCell::new(scope))) {
+                Ok(_) | Err(Unwind::Continue(_)) => {}
+                Err(Unwind::Break(_)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Value::Nil)
+    }
+
+    /// Evaluates the first arm whose pattern matches and whose guard holds.
+    /// Pattern bindings live in a scope of their own, shared by guard and body.
+    fn match_arms(
+        &mut self,
+        scrutinee: &Node,
+        arms: &[MatchArm],
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        let value = self.eval(scrutinee)?;
+
+        for arm in arms.iter() {
+            let mut bindings = vec![];
+            if !match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+
+            let mut scope = Environment::with_parent(self.env.clone());
+            for (name, value) in bindings {
+                scope.define(&name, value);
+            }
+            let previous = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+            let result = self.arm(arm);
+            self.env = previous;
+
+            if let Some(value) = result? {
+                return Ok(value);
+            }
+        }
+
+        Err(runtime_error(span, format!("No match arm matches {}", value.repr())).into())
+    }
+
+    /// Value of an arm's body, or `None` when its guard fails.
+    fn arm(&mut self, arm: &MatchArm) -> Result<Option<Value>, Unwind> {
+        if let Some(guard) = &arm.guard {
+            if !self.eval(guard)?.is_truthy() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(self.eval(&arm.body)?))
+    }
+
+    /// Runs a block in a new scope nested in the current one.
+    fn scoped_block(&mut self, block: &Block) -> Result<Value, Unwind> {
+        let scope = Environment::with_parent(self.env.clone());
+        self.execute_block(block, Rc::new(RefCell::new(scope)))
+    }
+
+    /// Evaluates an expression in `scope`, restoring the current scope
+    /// afterwards.
+    fn eval_in(&mut self, node: &Node, scope: Rc<RefCell<Environment>>) -> Result<Value, Unwind> {
+        let previous = std::mem::replace(&mut self.env, scope);
+        let result = self.eval(node);
+        self.env = previous;
+        result
+    }
+
+    /// Runs a block in `scope`, restoring the current scope afterwards.
+    fn execute_block(
+        &mut self,
+        block: &Block,
+        scope: Rc<RefCell<Environment>>,
+    ) -> Result<Value, Unwind> {
+        let previous = std::mem::replace(&mut self.env, scope);
+        let result = self.block_body(block);
+        self.env = previous;
+        result
+    }
+
+    fn block_body(&mut self, block: &Block) -> Result<Value, Unwind> {
+        for node in block.body.iter() {
+            self.eval(node)?;
+        }
+        match &block.value {
+            Some(value) => self.eval(value),
+            None => Ok(Value::Nil),
+        }
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) -> Result<Value, Unwind> {
+        let value = self.eval(&declaration.value)?;
+        let lock = TypeLock::new(
+            declaration.annotation.as_ref(),
+            declaration.inferred,
+            &value,
+        );
+
+        // the type checker only sees values whose type is known statically
+        if let Some(lock) = &lock {
+            if !lock.accepts(&value) {
+                let span = declaration.value.span;
+                return Err(mismatch(lock, &declaration.symbol, &value, span).into());
+            }
+        }
+        self.env
+            .borrow_mut()
+            .declare(&declaration.symbol, value, declaration.mutable, lock);
+        Ok(Value::Nil)
+    }
+
+    /// `target = value` or `target += value`. Variables are checked against
+    /// their binding, fields and elements are updated in place.
+    fn assign(
+        &mut self,
+        target: &Node,
+        op: &Option<Operator>,
+        node: &Node,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        match &target.kind {
+            NodeKind::Name(name) => {
+                let current = match op {
+                    Some(_) => Some(self.eval(target)?),
+                    None => None,
+                };
+                let value = self.eval(node)?;
+                let value = compound(op, current, value, span)?;
+                assign_name(&self.env, name, value.clone(), target.span, node.span, span)?;
+                Ok(value)
+            }
+            NodeKind::Get { object, field, .. } => {
+                let object = self.eval(object)?;
+                let current = match op {
+                    Some(_) => Some(get(&self.globals, object.clone(), field, target.span)?),
+                    None => None,
+                };
+                let value = self.eval(node)?;
+                let value = compound(op, current, value, span)?;
+                set_field(&object, field, value.clone())
+                    .map_err(|message| runtime_error(target.span, message))?;
+                Ok(value)
+            }
+            NodeKind::Index { object, index } => {
+                let object = self.eval(object)?;
+                let i = self.eval(index)?;
+                let current = match op {
+                    Some(_) => Some(
+                        index_value(&object, &i)
+                            .map_err(|message| runtime_error(index.span, message))?,
+                    ),
+                    None => None,
+                };
+                let value = self.eval(node)?;
+                let value = compound(op, current, value, span)?;
+                set_index(&object, &i, value.clone())
+                    .map_err(|message| runtime_error(index.span, message))?;
+                Ok(value)
+            }
+            _ => Err(runtime_error(target.span, "Invalid assignment target".to_string()).into()),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: &Operator,
+        lhs: &Node,
+        rhs: &Node,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        // logical operators short circuit, so the right side is evaluated lazily
+        match op {
+            Operator::And => {
+                let l = self.eval(lhs)?;
+                return Ok(Value::Boolean(l.is_truthy() && self.eval(rhs)?.is_truthy()));
+            }
+            Operator::Or => {
+                let l = self.eval(lhs)?;
+                return Ok(Value::Boolean(l.is_truthy() || self.eval(rhs)?.is_truthy()));
+            }
+            Operator::Coalesce => {
+                return match self.eval(lhs)? {
+                    Value::Nil => self.eval(rhs),
+                    l => Ok(l),
+                };
+            }
+            _ => {}
+        }
+
+        let l = self.eval(lhs)?;
+        let r = self.eval(rhs)?;
+        binary_op(op, l, r).map_err(|message| runtime_error(span, message).into())
+    }
+}
+
+pub fn struct_type(env: &Rc<RefCell<Environment>>, declaration: &Rc<StructDecl>) -> StructType {
+    let methods = declaration
+        .methods
+        .iter()
+        .map(|m| {
+            Rc::new(FunctionValue {
+                declaration: m.clone(),
+                closure: env.clone(),
+            })
+        })
+        .collect();
+
+    StructType {
+        declaration: declaration.clone(),
+        fields: RefCell::new(
+            declaration
+                .fields
+                .iter()
+                .map(|field| FieldType {
+                    field: field.clone(),
+                    scope: env.clone(),
+                })
+                .collect(),
+        ),
+        methods: RefCell::new(methods),
+        implements: RefCell::new(vec![]),
+    }
+}
+
+/// Implements a `shared` interface on a struct. Only the interface's own
+/// members are added, and interfaces can only be implemented on structs,
+/// so nothing is ever inherited through more than one layer.
+pub fn implement(env: &Rc<RefCell<Environment>>, declaration: &ImplDecl) -> Result<(), Unwind> {
+    let span = declaration.span;
+    let shared = match env.borrow().get(&declaration.shared) {
+        Some(Value::Shared(s)) => s,
+        Some(v) => {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "'{}' is a {}, not a shared interface",
+                    declaration.shared,
+                    v.type_name()
+                ),
+            )
+            .into())
+        }
+        None => {
+            return Err(runtime_error(
+                span,
+                format!("Undefined shared interface '{}'", declaration.shared),
+            )
+            .into())
+        }
+    };
+
+    let target = match env.borrow().get(&declaration.target) {
+        Some(Value::Struct(s)) => s,
+        Some(Value::Shared(_)) => {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "Cannot implement '{}' on shared '{}'",
+                    shared.name(),
+                    declaration.target
+                ),
+            )
+            .with_help("shared interfaces can only be implemented on structs")
+            .into())
+        }
+        Some(v) => {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "'{}' is a {}, not a struct",
+                    declaration.target,
+                    v.type_name()
+                ),
+            )
+            .into())
+        }
+        None => {
+            return Err(
+                runtime_error(span, format!("Undefined struct '{}'", declaration.target)).into(),
+            )
+        }
+    };
+
+    if target
+        .implements
+        .borrow()
+        .iter()
+        .any(|s| Rc::ptr_eq(s, &shared))
+    {
+        return Err(runtime_error(
+            span,
+            format!("'{}' already implements '{}'", target.name(), shared.name()),
+        )
+        .into());
+    }
+
+    let interface = &shared.declaration;
+    for method in declaration.methods.iter() {
+        let Some(required) = interface.methods.iter().find(|m| m.name == method.name) else {
+            return Err(runtime_error(
+                method.span,
+                format!(
+                    "'{}' is not a method of shared '{}'",
+                    method.name,
+                    shared.name()
+                ),
+            )
+            .with_help("methods outside the interface belong in the struct declaration")
+            .into());
+        };
+        if required.params.len() != method.params.len() {
+            return Err(runtime_error(
+                method.span,
+                format!(
+                    "Method '{}' of shared '{}' takes {} parameter(s) but {} were given",
+                    method.name,
+                    shared.name(),
+                    required.params.len(),
+                    method.params.len()
+                ),
+            )
+            .into());
+        }
+    }
+
+    for field in declaration.overrides.iter() {
+        if !interface.fields.iter().any(|f| f.name == field.name) {
+            return Err(runtime_error(
+                field.span,
+                format!(
+                    "'{}' is not a field of shared '{}'",
+                    field.name,
+                    shared.name()
+                ),
+            )
+            .into());
+        }
+    }
+
+    for required in interface.methods.iter().filter(|m| m.body.is_none()) {
+        if !declaration.methods.iter().any(|m| m.name == required.name) {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "Missing method '{}' required by shared '{}'",
+                    required.name,
+                    shared.name()
+                ),
+            )
+            .into());
+        }
+    }
+
+    // members of the interface may not clash with the struct's own members
+    // or with those of another interface
+    let members = interface
+        .fields
+        .iter()
+        .map(|f| &f.name)
+        .chain(interface.methods.iter().map(|m| &m.name));
+    for member in members {
+        if target.has_member(member) {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "'{member}' of shared '{}' conflicts with a member of '{}'",
+                    shared.name(),
+                    target.name()
+                ),
+            )
+            .into());
+        }
+    }
+
+    let mut fields = target.fields.borrow_mut();
+    for field in interface.fields.iter() {
+        let mut field = field.clone();
+        // an override is evaluated where the impl is, the interface's own
+        // default where the interface is
+        let scope = match declaration.overrides.iter().find(|o| o.name == field.name) {
+            Some(o) => {
+                field.default = Some(o.value.clone());
+                env.clone()
+            }
+            None => shared.closure.clone(),
+        };
+        fields.push(FieldType { field, scope });
+    }
+
+    let mut methods = target.methods.borrow_mut();
+    for method in interface.methods.iter() {
+        let value = match declaration.methods.iter().find(|m| m.name == method.name) {
+            Some(m) => FunctionValue {
+                declaration: m.clone(),
+                closure: env.clone(),
+            },
+            None => FunctionValue {
+                declaration: method.clone(),
+                closure: shared.closure.clone(),
+            },
+        };
+        methods.push(Rc::new(value));
+    }
+
+    target.implements.borrow_mut().push(shared.clone());
+    Ok(())
+}
+
+fn lookup_struct(
+    env: &Rc<RefCell<Environment>>,
+    name: &str,
+    span: Span,
+) -> Result<Rc<StructType>, Unwind> {
+    match env.borrow().get(name) {
+        Some(Value::Struct(s)) => Ok(s),
+        Some(v) => Err(runtime_error(
+            span,
+            format!("'{name}' is a {}, not a struct", v.type_name()),
+        )
+        .into()),
+        None => Err(runtime_error(span, format!("Undefined struct '{name}'")).into()),
+    }
+}
+
+/// `Struct::function` or `Enum::Variant`. Unit variants are values right
+/// away, tuple variants are constructors to call.
+pub fn path(
+    env: &Rc<RefCell<Environment>>,
+    base: &str,
+    member: &str,
+    span: Span,
+) -> Result<Value, Unwind> {
+    let enum_type = match env.borrow().get(base) {
+        Some(Value::Enum(e)) => e,
+        Some(Value::Struct(struct_type)) => {
+            return match struct_type.method(member) {
+                Some(method) => Ok(Value::Function(method)),
+                None => Err(runtime_error(
+                    span,
+                    format!("Struct '{base}' has no function '{member}'"),
+                )
+                .into()),
+            }
+        }
+        Some(v) => {
+            return Err(runtime_error(
+                span,
+                format!("'{base}' is a {}, not a struct or enum", v.type_name()),
+            )
+            .into())
+        }
+        None => return Err(runtime_error(span, format!("Undefined name '{base}'")).into()),
+    };
+
+    let Some(tag) = enum_type.tag(member) else {
+        return Err(runtime_error(span, format!("Enum '{base}' has no variant '{member}'")).into());
+    };
+    match &enum_type.declaration.variants[tag].kind {
+        VariantKind::Unit => Ok(Value::Variant(Rc::new(VariantValue {
+            enum_type: enum_type.clone(),
+            tag,
+            data: VariantData::Unit,
+        }))),
+        VariantKind::Tuple(_) => Ok(Value::Constructor(enum_type.clone(), tag)),
+        VariantKind::Struct(_) => Err(runtime_error(
+            span,
+            format!("Variant '{base}::{member}' has fields"),
+        )
+        .with_help(&format!("build it with `{base}::{member} {{ ... }}`"))
+        .into()),
+    }
+}
+
+/// A field of a literal, evaluated in source order, with the span of its value.
+pub type GivenField = (String, Value, Span);
+
+/// Evaluates the default of a field in the scope it was declared in.
+pub type EvalDefault<'a> =
+    dyn FnMut(&Rc<Node>, &Rc<RefCell<Environment>>) -> Result<Value, Unwind> + 'a;
+
+/// Builds a struct like variant, every field must be given exactly once.
+pub fn variant_literal(
+    env: &Rc<RefCell<Environment>>,
+    name: &str,
+    variant: &str,
+    given: Vec<GivenField>,
+    span: Span,
+) -> Result<Value, Unwind> {
+    let enum_type = match env.borrow().get(name) {
+        Some(Value::Enum(e)) => e,
+        _ => return Err(runtime_error(span, format!("Undefined enum '{name}'")).into()),
+    };
+    let Some(tag) = enum_type.tag(variant) else {
+        return Err(
+            runtime_error(span, format!("Enum '{name}' has no variant '{variant}'")).into(),
+        );
+    };
+    let VariantKind::Struct(declared) = &enum_type.declaration.variants[tag].kind else {
+        return Err(runtime_error(
+            span,
+            format!("Variant '{name}::{variant}' has no named fields"),
+        )
+        .into());
+    };
+
+    for (field, _, value_span) in given.iter() {
+        if !declared.iter().any(|f| &f.name == field) {
+            return Err(runtime_error(
+                *value_span,
+                format!("Variant '{name}::{variant}' has no field '{field}'"),
+            )
+            .into());
+        }
+    }
+
+    let mut values = Vec::with_capacity(declared.len());
+    for field in declared.iter() {
+        let Some((_, value, _)) = given.iter().find(|(n, _, _)| n == &field.name) else {
+            return Err(runtime_error(
+                span,
+                format!("Missing field '{}' in '{name}::{variant}'", field.name),
+            )
+            .into());
+        };
+        values.push((field.name.clone(), value.clone()));
+    }
+
+    Ok(Value::Variant(Rc::new(VariantValue {
+        enum_type: enum_type.clone(),
+        tag,
+        data: VariantData::Struct(values),
+    })))
+}
+
+/// Builds an instance, every declared field must either be given or have a
+/// default. Defaults are evaluated by `default` in the scope they were
+/// declared in, after the given fields.
+pub fn struct_literal(
+    env: &Rc<RefCell<Environment>>,
+    name: &str,
+    given: Vec<GivenField>,
+    span: Span,
+    default: &mut EvalDefault,
+) -> Result<Value, Unwind> {
+    let struct_type = lookup_struct(env, name, span)?;
+    let declared = struct_type.fields.borrow().clone();
+
+    for (field, _, value_span) in given.iter() {
+        if !declared.iter().any(|f| &f.field.name == field) {
+            return Err(runtime_error(
+                *value_span,
+                format!("Struct '{name}' has no field '{field}'"),
+            )
+            .into());
+        }
+    }
+
+    let mut values = Vec::with_capacity(declared.len());
+    for FieldType { field, scope } in declared.iter() {
+        let given = given.iter().find(|(n, _, _)| n == &field.name);
+        let (value, value_span) = match (given, &field.default) {
+            (Some((_, value, value_span)), _) => (value.clone(), *value_span),
+            (None, Some(node)) => (default(node, scope)?, span),
+            (None, None) if field.optional => (Value::Nil, span),
+            (None, None) => {
+                return Err(runtime_error(
+                    span,
+                    format!("Missing field '{}' in '{name}' literal", field.name),
+                )
+                .into())
+            }
+        };
+        if let Some(t) = struct_type.rejects(&field.name, &value) {
+            return Err(runtime_error(value_span, field_mismatch(&t, &field.name, &value)).into());
+        }
+        values.push((field.name.clone(), value));
+    }
+
+    Ok(Value::Instance(Rc::new(RefCell::new(Instance {
+        struct_type: struct_type.clone(),
+        fields: values,
+    }))))
+}
+
+/// Reads a field, or binds a method to the instance it was accessed on.
+/// Methods of strings, collections and results are looked up in `globals`.
+pub fn get(
+    globals: &Rc<RefCell<Environment>>,
+    object: Value,
+    field: &str,
+    span: Span,
+) -> Result<Value, Unwind> {
+    let instance = match &object {
+        Value::Instance(i) => i.clone(),
+        Value::Variant(v) => {
+            // `result.unwrap()` calls the native `unwrap(result)`
+            if let (None, Some(Value::Native(native))) = (v.get(field), globals.borrow().get(field))
+            {
+                return Ok(Value::Native(Rc::new(native.bind(object.clone()))));
+            }
+            return match v.get(field) {
+                Some(value) => Ok(value.clone()),
+                None => Err(runtime_error(
+                    span,
+                    format!(
+                        "'{}::{}' has no field '{field}'",
+                        v.enum_type.name(),
+                        v.name()
+                    ),
+                )
+                .into()),
+            };
+        }
+        v => {
+            // `"a,b".split(",")` calls the native `split("a,b", ",")`
+            if let Some(Value::Native(native)) = globals.borrow().get(field) {
+                return Ok(Value::Native(Rc::new(native.bind(object.clone()))));
+            }
+            return Err(runtime_error(
+                span,
+                format!(
+                    "Cannot access '{field}' on a value of type {}",
+                    v.type_name()
+                ),
+            )
+            .into());
+        }
+    };
+
+    if let Some(value) = instance.borrow().get(field) {
+        return Ok(value.clone());
+    }
+
+    let method = instance.borrow().struct_type.method(field);
+    match method {
+        Some(method) => Ok(Value::Function(bind(&method, &instance))),
+        None => Err(runtime_error(
+            span,
+            format!(
+                "'{}' has no field or method '{field}'",
+                instance.borrow().struct_type.name()
+            ),
+        )
+        .into()),
+    }
+}
+
+/// Applies an eagerly evaluated binary operator to two values. Errors are
+/// returned as plain messages for the caller to attach a span to.
+pub fn binary_op(op: &Operator, l: Value, r: Value) -> Result<Value, String> {
+    let value = match (op, &l, &r) {
+        (Operator::Equal, _, _) => Value::Boolean(l == r),
+        (Operator::NotEqual, _, _) => Value::Boolean(l != r),
+        (Operator::Coalesce, Value::Nil, _) => r,
+        (Operator::Coalesce, _, _) => l,
+
+        (Operator::Add, Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+        (Operator::Add, Value::String(_), _) | (Operator::Add, _, Value::String(_)) => {
+            Value::String(format!("{l}{r}"))
+        }
+        (Operator::Sub, Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+        (Operator::Mult, Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+        (Operator::Div, Value::Number(_), Value::Number(b)) if *b == 0.0 => {
+            return Err("Division by zero".to_string());
+        }
+        (Operator::Div, Value::Number(a), Value::Number(b)) => Value::Number(a / b),
+        (Operator::Range, Value::Number(a), Value::Number(b)) => Value::Range(*a, *b),
+        (Operator::BitOr, Value::Number(a), Value::Number(b))
+            if a.fract() == 0.0 && b.fract() == 0.0 =>
+        {
+            Value::Number((*a as i64 | *b as i64) as f64)
+        }
+
+        (Operator::Greater, Value::Number(a), Value::Number(b)) => Value::Boolean(a > b),
+        (Operator::GreaterEqual, Value::Number(a), Value::Number(b)) => Value::Boolean(a >= b),
+        (Operator::Less, Value::Number(a), Value::Number(b)) => Value::Boolean(a < b),
+        (Operator::LessEqual, Value::Number(a), Value::Number(b)) => Value::Boolean(a <= b),
+        (Operator::Greater, Value::String(a), Value::String(b)) => Value::Boolean(a > b),
+        (Operator::GreaterEqual, Value::String(a), Value::String(b)) => Value::Boolean(a >= b),
+        (Operator::Less, Value::String(a), Value::String(b)) => Value::Boolean(a < b),
+        (Operator::LessEqual, Value::String(a), Value::String(b)) => Value::Boolean(a <= b),
+        (Operator::Greater, Value::Char(a), Value::Char(b)) => Value::Boolean(a > b),
+        (Operator::GreaterEqual, Value::Char(a), Value::Char(b)) => Value::Boolean(a >= b),
+        (Operator::Less, Value::Char(a), Value::Char(b)) => Value::Boolean(a < b),
+        (Operator::LessEqual, Value::Char(a), Value::Char(b)) => Value::Boolean(a <= b),
+
+        _ => {
+            return Err(format!(
+                "Unsupported operand types for {}: {} and {}",
+                op,
+                l.type_name(),
+                r.type_name()
+            ))
+        }
+    };
+    Ok(value)
+}
+
+/// Reads `object[index]`. A range index slices arrays, tuples and strings.
+/// Errors are returned as plain messages for the caller to attach a span to.
+pub fn index_value(object: &Value, index: &Value) -> Result<Value, String> {
+    if let Value::Map(entries) = object {
+        return match entries.borrow().iter().find(|(k, _)| k == index) {
+            Some((_, value)) => Ok(value.clone()),
+            None => Err(format!("Key {} not found in map", index.repr())),
+        };
+    }
+
+    let len = match object {
+        Value::Array(items) => items.borrow().len(),
+        Value::Tuple(items) => items.len(),
+        Value::String(s) => s.chars().count(),
+        v => return Err(format!("Cannot index a value of type {}", v.type_name())),
+    };
+
+    if let Value::Range(start, end) = index {
+        let start = position(*start)?;
+        let end = position(*end)?;
+        if start > end || end > len {
+            return Err(format!(
+                "Slice {}..{} out of bounds for {} of length {len}",
+                start,
+                end,
+                object.type_name()
+            ));
+        }
+        let slice = match object {
+            Value::Array(items) => {
+                Value::Array(Rc::new(RefCell::new(items.borrow()[start..end].to_vec())))
+            }
+            Value::Tuple(items) => Value::Tuple(Rc::new(items[start..end].to_vec())),
+            Value::String(s) => Value::String(s.chars().skip(start).take(end - start).collect()),
+            _ => unreachable!(),
+        };
+        return Ok(slice);
+    }
+
+    let i = match index {
+        Value::Number(n) => position(*n)?,
+        v => {
+            return Err(format!(
+                "Cannot index a {} with a value of type {}",
+                object.type_name(),
+                v.type_name()
+            ))
+        }
+    };
+    if i >= len {
+        return Err(format!(
+            "Index {i} out of bounds for {} of length {len}",
+            object.type_name()
+        ));
+    }
+
+    let value = match object {
+        Value::Array(items) => items.borrow()[i].clone(),
+        Value::Tuple(items) => items[i].clone(),
+        Value::String(s) => Value::Char(s.chars().nth(i).unwrap_or_default()),
+        _ => unreachable!(),
+    };
+    Ok(value)
+}
+
+/// Rebinds the variable `name`, reporting why when that is not allowed.
+/// `target` and `value` are the spans of the two sides, `span` that of the
+/// whole assignment.
+pub fn assign_name(
+    env: &Rc<RefCell<Environment>>,
+    name: &str,
+    value: Value,
+    target: Span,
+    value_span: Span,
+    span: Span,
+) -> Result<(), CompilerError> {
+    let result = env.borrow_mut().assign(name, value.clone());
+    match result {
+        Ok(()) => Ok(()),
+        Err(AssignError::Undefined) => Err(runtime_error(
+            target,
+            format!("Undefined variable '{name}'"),
+        )),
+        Err(AssignError::Immutable) => Err(immutable(name, span)),
+        Err(AssignError::Mismatch(lock)) => {
+            let mut e = mismatch(&lock, name, &value, value_span);
+            if let TypeLock::Inferred(_) = lock {
+                e = e.with_help(&format!(
+                    "'{name}' was declared with `:=`, which keeps the type of its first value"
+                ));
+            }
+            Err(e)
+        }
+    }
+}
+
+/// An assignment to a variable declared without `mut`.
+pub fn immutable(name: &str, span: Span) -> CompilerError {
+    runtime_error(
+        span,
+        format!("Cannot assign twice to immutable variable '{name}'"),
+    )
+    .with_help("only variables declared with `let mut` can be assigned")
+}
+
+/// The items a `for` loop walks. Collections are iterated over a copy, so
+/// the body may change them. Errors are returned as plain messages for the
+/// caller to attach a span to.
+pub fn iterate(value: &Value) -> Result<Box<dyn Iterator<Item = Value>>, String> {
+    let items: Box<dyn Iterator<Item = Value>> = match value {
+        Value::Range(start, end) => {
+            let end = *end;
+            Box::new(
+                std::iter::successors(Some(*start), |i| Some(i + 1.0))
+                    .take_while(move |i| *i < end)
+                    .map(Value::Number),
+            )
+        }
+        Value::Array(items) => Box::new(items.borrow().clone().into_iter()),
+        Value::Tuple(items) => Box::new(items.as_ref().clone().into_iter()),
+        Value::String(s) => Box::new(s.chars().collect::<Vec<_>>().into_iter().map(Value::Char)),
+        v => {
+            return Err(format!(
+                "Cannot iterate over a value of type {}",
+                v.type_name()
+            ))
+        }
+    };
+    Ok(items)
+}
+
+pub fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Float(f) => Value::Number(*f as f64),
+        Literal::BigFloat(f) => Value::Number(*f),
+        Literal::String(s) => Value::String(s.clone()),
+        Literal::Char(c) => Value::Char(*c),
+        Literal::Boolean(b) => Value::Boolean(*b),
+        Literal::Nil => Value::Nil,
+    }
+}
+
+/// Applies the operator of a compound assignment to the current value.
+fn compound(
+    op: &Option<Operator>,
+    current: Option<Value>,
+    value: Value,
+    span: Span,
+) -> Result<Value, CompilerError> {
+    match (op, current) {
+        (Some(op), Some(current)) => {
+            binary_op(op, current, value).map_err(|message| runtime_error(span, message))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Writes `object.field`. Only fields an instance already has can be set.
+pub fn set_field(object: &Value, field: &str, value: Value) -> Result<(), String> {
+    match object {
+        Value::Instance(instance) => {
+            // checked before borrowing, the value may be this very instance
+            let struct_type = instance.borrow().struct_type.clone();
+            if let Some(t) = struct_type.rejects(field, &value) {
+                return Err(field_mismatch(&t, field, &value));
+            }
+            let mut instance = instance.borrow_mut();
+            let name = struct_type.name().to_string();
+            match instance.fields.iter_mut().find(|(n, _)| n == field) {
+                Some((_, slot)) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(format!("'{name}' has no field '{field}'")),
+            }
+        }
+        Value::Variant(v) => Err(format!(
+            "Fields of '{}::{}' cannot be assigned",
+            v.enum_type.name(),
+            v.name()
+        )),
+        v => Err(format!(
+            "Cannot assign to '{field}' on a value of type {}",
+            v.type_name()
+        )),
+    }
+}
+
+/// Writes `object[index]`. Arrays are updated in bounds, maps insert missing
+/// keys. Errors are returned as plain messages for the caller to attach a
+/// span to.
+pub fn set_index(object: &Value, index: &Value, value: Value) -> Result<(), String> {
+    match (object, index) {
+        (Value::Map(entries), _) => {
+            let mut entries = entries.borrow_mut();
+            match entries.iter_mut().find(|(k, _)| k == index) {
+                Some((_, slot)) => *slot = value,
+                None => entries.push((index.clone(), value)),
+            }
+            Ok(())
+        }
+        (Value::Array(items), Value::Number(n)) => {
+            let i = position(*n)?;
+            let mut items = items.borrow_mut();
+            let len = items.len();
+            match items.get_mut(i) {
+                Some(slot) => {
+                    *slot = value;
+                    Ok(())
+                }
+                None => Err(format!("Index {i} out of bounds for array of length {len}")),
+            }
+        }
+        (Value::Array(_), v) => Err(format!(
+            "Cannot assign to an array element with an index of type {}",
+            v.type_name()
+        )),
+        (Value::Tuple(_) | Value::String(_), _) => Err(format!(
+            "Elements of a {} cannot be assigned",
+            object.type_name()
+        )),
+        (v, _) => Err(format!("Cannot index a value of type {}", v.type_name())),
+    }
+}
+
+/// Converts a number used as an index or slice bound to a position.
+fn position(n: f64) -> Result<usize, String> {
+    match n.fract() == 0.0 && n >= 0.0 {
+        true => Ok(n as usize),
+        false => Err(format!(
+            "Index must be a non-negative whole number, found {}",
+            Value::Number(n)
+        )),
+    }
+}
+
+/// Matches a value against a pattern, collecting the names it binds.
+pub fn match_pattern(
+    pattern: &Pattern,
+    value: &Value,
+    bindings: &mut Vec<(String, Value)>,
+) -> bool {
+    match &pattern.kind {
+        PatternKind::Wildcard => true,
+        PatternKind::Binding(name) => {
+            bindings.push((name.clone(), value.clone()));
+            true
+        }
+        PatternKind::Literal(literal) => literal_value(literal) == *value,
+        PatternKind::Range(start, end) => match (literal_value(start), literal_value(end), value) {
+            (Value::Number(a), Value::Number(b), Value::Number(n)) => a <= *n && *n < b,
+            (Value::Char(a), Value::Char(b), Value::Char(c)) => a <= *c && *c < b,
+            _ => false,
+        },
+        PatternKind::Struct { name, fields } => {
+            let Value::Instance(instance) = value else {
+                return false;
+            };
+            let instance = instance.borrow();
+            instance.struct_type.name() == name
+                && fields.iter().all(|(field, pattern)| {
+                    instance
+                        .get(field)
+                        .is_some_and(|v| match_pattern(pattern, v, bindings))
+                })
+        }
+        PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } => {
+            let Value::Variant(value) = value else {
+                return false;
+            };
+            if value.enum_type.name() != enum_name || value.name() != variant {
+                return false;
+            }
+            match (fields, &value.data) {
+                // a bare variant pattern only checks the tag
+                (VariantPattern::Unit, _) => true,
+                (VariantPattern::Tuple(patterns), VariantData::Tuple(values)) => {
+                    patterns.len() == values.len()
+                        && patterns
+                            .iter()
+                            .zip(values)
+                            .all(|(p, v)| match_pattern(p, v, bindings))
+                }
+                (VariantPattern::Struct(patterns), VariantData::Struct(_)) => {
+                    patterns.iter().all(|(field, pattern)| {
+                        value
+                            .get(field)
+                            .is_some_and(|v| match_pattern(pattern, v, bindings))
+                    })
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Calls a tuple variant's constructor, `Shape::Circle(2)`.
+pub fn construct(
+    enum_type: Rc<EnumType>,
+    tag: usize,
+    args: Vec<Value>,
+    span: Span,
+) -> Result<Value, Unwind> {
+    let arity = match &enum_type.declaration.variants[tag].kind {
+        VariantKind::Tuple(types) => types.len(),
+        _ => 0,
+    };
+    if args.len() != arity {
+        return Err(runtime_error(
+            span,
+            format!(
+                "Variant '{}::{}' expects {} value(s) but got {}",
+                enum_type.name(),
+                enum_type.variant_name(tag),
+                arity,
+                args.len()
+            ),
+        )
+        .into());
+    }
+
+    Ok(Value::Variant(Rc::new(VariantValue {
+        enum_type,
+        tag,
+        data: VariantData::Tuple(args),
+    })))
+}
+
+/// Binds a method to an instance. The method body sees `this` along with
+/// every field of the instance as a plain name.
+fn bind(method: &Rc<FunctionValue>, instance: &Rc<RefCell<Instance>>) -> Rc<FunctionValue> {
+    let scope = Environment::with_receiver(method.closure.clone(), instance.clone());
+    Rc::new(FunctionValue {
+        declaration: method.declaration.clone(),
+        closure: Rc::new(RefCell::new(scope)),
+    })
+}
+
+/// Settles a result of top level evaluation, where nothing is left to unwind to.
+fn finish(result: Result<Value, Unwind>) -> Result<Value, CompilerError> {
+    match result.map_err(Unwind::outside_loop) {
+        Ok(v) | Err(Unwind::Return(v)) => Ok(v),
+        Err(Unwind::Error(e)) => Err(e),
+        Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+    }
+}
+
+/// Address of a local of the current call, which is how far down the host
+/// stack evaluation has come.
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::ptr::addr_of!(marker) as usize
+}
+
+pub fn runtime_error(span: Span, message: String) -> CompilerError {
+    CompilerError::at(span, message, ErrorTypes::RuntimeError)
+}
+
+/// Holds the arguments of a call to the annotations of their parameters.
+pub fn check_args(function: &FunctionValue, args: &[Value], span: Span) -> Result<(), Unwind> {
+    for (param, arg) in function.declaration.params.iter().zip(args) {
+        match &param.annotation {
+            Some(annotation) if !arg.is_a(annotation) => {
+                return Err(runtime_error(
+                    span,
+                    format!(
+                        "Mismatched types: expected {annotation} for '{}', found {}",
+                        param.name,
+                        arg.type_name()
+                    ),
+                )
+                .into())
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Holds the value a call returns to the function's return type.
+pub fn check_return(function: &FunctionValue, value: &Value, span: Span) -> Result<(), Unwind> {
+    match &function.declaration.return_type {
+        Some(annotation) if !value.is_a(annotation) => Err(runtime_error(
+            span,
+            format!(
+                "Mismatched types: expected {annotation} for the return value of '{}', found {}",
+                function.name(),
+                value.type_name()
+            ),
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// A value that does not fit the type its binding is locked to.
+fn field_mismatch(t: &Type, field: &str, value: &Value) -> String {
+    format!(
+        "Mismatched types: expected {t} for field '{field}', found {}",
+        value.type_name()
+    )
+}
+
+pub fn mismatch(lock: &TypeLock, name: &str, value: &Value, span: Span) -> CompilerError {
+    runtime_error(
+        span,
+        format!(
+            "Mismatched types: expected {lock} for '{name}', found {}",
+            value.type_name()
+        ),
+    )
+}
@@ -0,0 +1,6 @@
+pub mod convert;
+pub mod environment;
+pub mod interpreter;
+pub mod native;
+pub mod prelude;
+pub mod value;
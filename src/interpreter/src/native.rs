@@ -0,0 +1,86 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+
+use error::types::Span;
+
+use crate::{
+    interpreter::{runtime_error, Unwind},
+    value::Value,
+};
+
+/// Signature of a function implemented in Rust. It gets the backend running
+/// the program, so it can call back into Ion functions, and the span of the
+/// call for errors.
+pub type NativeFn = dyn Fn(&mut dyn Caller, Vec<Value>, Span) -> Result<Value, Unwind>;
+
+/// A backend that native functions can call back into, the tree-walking
+/// interpreter or the bytecode VM.
+pub trait Caller {
+    fn call(&mut self, callee: Value, args: Vec<Value>, span: Span) -> Result<Value, Unwind>;
+}
+
+/// A function implemented in Rust and callable from Ion.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    /// `None` accepts any number of arguments.
+    pub arity: Option<usize>,
+    pub func: Rc<NativeFn>,
+    /// Value the function was accessed on, as in `"a,b".split(",")`. It is
+    /// passed as the first argument.
+    pub receiver: Option<Value>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: Option<usize>, func: Rc<NativeFn>) -> NativeFunction {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            func,
+            receiver: None,
+        }
+    }
+
+    /// Calls the function, passing the receiver first and checking the arity.
+    pub fn invoke(
+        &self,
+        caller: &mut dyn Caller,
+        mut args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, Unwind> {
+        if let Some(receiver) = &self.receiver {
+            args.insert(0, receiver.clone());
+        }
+        if let Some(arity) = self.arity {
+            if args.len() != arity {
+                return Err(runtime_error(
+                    span,
+                    format!(
+                        "Function '{}' expects {} argument(s) but got {}",
+                        self.name,
+                        arity,
+                        args.len()
+                    ),
+                )
+                .into());
+            }
+        }
+        (self.func)(caller, args, span)
+    }
+
+    /// The same function with `receiver` as its first argument.
+    pub fn bind(&self, receiver: Value) -> NativeFunction {
+        NativeFunction {
+            receiver: Some(receiver),
+            ..self.clone()
+        }
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
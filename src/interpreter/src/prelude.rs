@@ -0,0 +1,255 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use error::types::{CompilerError, ErrorTypes, Span};
+use parser::types::EnumDecl;
+
+use crate::{
+    environment::Environment,
+    interpreter::{runtime_error, Unwind},
+    native::{Caller, NativeFunction},
+    value::{EnumType, Value, VariantData, VariantValue},
+};
+
+/// Installs the native functions every program starts with. Functions taking
+/// a string or an array first can also be called as methods, `s.trim()`.
+pub fn install(globals: &mut Environment) {
+    // output
+    register(globals, "println", None, |_, args, _| {
+        println!("{}", join(&args));
+        Ok(Value::Nil)
+    });
+    register(globals, "print", None, |_, args, _| {
+        print!("{}", join(&args));
+        let _ = std::io::stdout().flush();
+        Ok(Value::Nil)
+    });
+
+    // strings
+    register(globals, "split", Some(2), |_, args, span| {
+        let s = string(&args[0], span)?;
+        let separator = string(&args[1], span)?;
+        let parts = s
+            .split(separator.as_str())
+            .map(|p| Value::String(p.to_string()))
+            .collect();
+        Ok(array(parts))
+    });
+    register(globals, "trim", Some(1), |_, args, span| {
+        Ok(Value::String(string(&args[0], span)?.trim().to_string()))
+    });
+    register(globals, "to_upper", Some(1), |_, args, span| {
+        Ok(Value::String(string(&args[0], span)?.to_uppercase()))
+    });
+    register(globals, "contains", Some(2), |_, args, span| {
+        let found = match (&args[0], &args[1]) {
+            (Value::String(s), Value::String(part)) => s.contains(part.as_str()),
+            (Value::String(s), Value::Char(c)) => s.contains(*c),
+            (Value::Array(items), value) => items.borrow().contains(value),
+            (Value::Tuple(items), value) => items.contains(value),
+            (Value::Map(entries), key) => entries.borrow().iter().any(|(k, _)| k == key),
+            (v, _) => return Err(mismatch("contains", "a string or collection", v, span)),
+        };
+        Ok(Value::Boolean(found))
+    });
+
+    // math
+    register(globals, "abs", Some(1), |_, args, span| {
+        Ok(Value::Number(number(&args[0], span)?.abs()))
+    });
+    register(globals, "floor", Some(1), |_, args, span| {
+        Ok(Value::Number(number(&args[0], span)?.floor()))
+    });
+    register(globals, "sqrt", Some(1), |_, args, span| {
+        let n = number(&args[0], span)?;
+        if n < 0.0 {
+            return Err(runtime_error(
+                span,
+                format!("Cannot take the square root of {}", Value::Number(n)),
+            )
+            .into());
+        }
+        Ok(Value::Number(n.sqrt()))
+    });
+    register(globals, "min", Some(2), |_, args, span| {
+        Ok(Value::Number(
+            number(&args[0], span)?.min(number(&args[1], span)?),
+        ))
+    });
+    register(globals, "max", Some(2), |_, args, span| {
+        Ok(Value::Number(
+            number(&args[0], span)?.max(number(&args[1], span)?),
+        ))
+    });
+
+    // collections
+    register(globals, "len", Some(1), |_, args, span| {
+        let len = match &args[0] {
+            Value::String(s) => s.chars().count(),
+            Value::Array(items) => items.borrow().len(),
+            Value::Tuple(items) => items.len(),
+            Value::Map(entries) => entries.borrow().len(),
+            v => return Err(mismatch("len", "a string or collection", v, span)),
+        };
+        Ok(Value::Number(len as f64))
+    });
+    register(globals, "push", Some(2), |_, args, span| match &args[0] {
+        Value::Array(items) => {
+            items.borrow_mut().push(args[1].clone());
+            Ok(Value::Nil)
+        }
+        v => Err(mismatch("push", "an array", v, span)),
+    });
+    register(globals, "map", Some(2), |caller, args, span| {
+        let mut mapped = vec![];
+        for item in items(&args[0], "map", span)? {
+            mapped.push(caller.call(args[1].clone(), vec![item], span)?);
+        }
+        Ok(array(mapped))
+    });
+    register(globals, "filter", Some(2), |caller, args, span| {
+        let mut kept = vec![];
+        for item in items(&args[0], "filter", span)? {
+            if caller
+                .call(args[1].clone(), vec![item.clone()], span)?
+                .is_truthy()
+            {
+                kept.push(item);
+            }
+        }
+        Ok(array(kept))
+    });
+    // reduce(xs, initial, |acc, x| ...)
+    register(globals, "reduce", Some(3), |caller, args, span| {
+        let mut acc = args[1].clone();
+        for item in items(&args[0], "reduce", span)? {
+            acc = caller.call(args[2].clone(), vec![acc, item], span)?;
+        }
+        Ok(acc)
+    });
+
+    // conversion
+    register(globals, "to_string", Some(1), |_, args, _| {
+        Ok(Value::String(args[0].to_string()))
+    });
+    // `nil` when the string is not a number
+    register(globals, "parse_number", Some(1), |_, args, span| {
+        let s = string(&args[0], span)?;
+        Ok(s.trim().parse::<f64>().map_or(Value::Nil, Value::Number))
+    });
+
+    // errors, `Ok(value)` and `Err(error)` are the variants of `Result`
+    let result = Rc::new(EnumType {
+        declaration: Rc::new(EnumDecl::result()),
+    });
+    globals.define("Result", Value::Enum(result.clone()));
+    globals.define("Ok", Value::Constructor(result.clone(), 0));
+    globals.define("Err", Value::Constructor(result.clone(), 1));
+
+    register(globals, "panic", Some(1), |_, args, span| {
+        Err(CompilerError::at(span, args[0].to_string(), ErrorTypes::Panic).into())
+    });
+    // runs `f`, turning a runtime error or panic into an `Err` with its message
+    register(
+        globals,
+        "try",
+        Some(1),
+        move |caller, args, span| match caller.call(args[0].clone(), vec![], span) {
+            Ok(value) if value.as_result().is_some() => Ok(value),
+            Ok(value) => Ok(variant(&result, 0, value)),
+            Err(Unwind::Error(e)) => Ok(variant(&result, 1, Value::String(e.message.clone()))),
+            Err(unwind) => Err(unwind),
+        },
+    );
+    register(globals, "is_ok", Some(1), |_, args, span| {
+        Ok(Value::Boolean(outcome(&args[0], "is_ok", span)?.is_ok()))
+    });
+    register(globals, "is_err", Some(1), |_, args, span| {
+        Ok(Value::Boolean(outcome(&args[0], "is_err", span)?.is_err()))
+    });
+    register(globals, "unwrap", Some(1), |_, args, span| {
+        match outcome(&args[0], "unwrap", span)? {
+            Ok(value) => Ok(value.clone()),
+            Err(e) => Err(runtime_error(span, format!("Called 'unwrap' on an error: {e}")).into()),
+        }
+    });
+    register(
+        globals,
+        "unwrap_or",
+        Some(2),
+        |_, args, span| match outcome(&args[0], "unwrap_or", span)? {
+            Ok(value) => Ok(value.clone()),
+            Err(_) => Ok(args[1].clone()),
+        },
+    );
+}
+
+/// Defines a native function in `globals`. `arity` of `None` accepts any
+/// number of arguments.
+pub fn register<F>(globals: &mut Environment, name: &str, arity: Option<usize>, func: F)
+where
+    F: Fn(&mut dyn Caller, Vec<Value>, Span) -> Result<Value, Unwind> + 'static,
+{
+    let native = NativeFunction::new(name, arity, Rc::new(func));
+    globals.define(name, Value::Native(Rc::new(native)));
+}
+
+/// Arguments of `print` and `println`, separated by spaces.
+fn join(args: &[Value]) -> String {
+    args.iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn variant(result: &Rc<EnumType>, tag: usize, value: Value) -> Value {
+    Value::Variant(Rc::new(VariantValue {
+        enum_type: result.clone(),
+        tag,
+        data: VariantData::Tuple(vec![value]),
+    }))
+}
+
+fn outcome<'v>(
+    value: &'v Value,
+    name: &str,
+    span: Span,
+) -> Result<Result<&'v Value, &'v Value>, Unwind> {
+    value
+        .as_result()
+        .ok_or_else(|| mismatch(name, "a Result", value, span))
+}
+
+fn array(items: Vec<Value>) -> Value {
+    Value::Array(Rc::new(RefCell::new(items)))
+}
+
+/// Copy of the items of an array or tuple, so callbacks may change the original.
+fn items(value: &Value, name: &str, span: Span) -> Result<Vec<Value>, Unwind> {
+    match value {
+        Value::Array(items) => Ok(items.borrow().clone()),
+        Value::Tuple(items) => Ok(items.as_ref().clone()),
+        v => Err(mismatch(name, "an array", v, span)),
+    }
+}
+
+fn string(value: &Value, span: Span) -> Result<String, Unwind> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        v => Err(runtime_error(span, format!("Expected a string, found {}", v.type_name())).into()),
+    }
+}
+
+fn number(value: &Value, span: Span) -> Result<f64, Unwind> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        v => Err(runtime_error(span, format!("Expected a number, found {}", v.type_name())).into()),
+    }
+}
+
+fn mismatch(name: &str, expected: &str, found: &Value, span: Span) -> Unwind {
+    runtime_error(
+        span,
+        format!("'{name}' expects {expected}, found {}", found.type_name()),
+    )
+    .into()
+}
@@ -0,0 +1,491 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug, Display, Formatter},
+    rc::Rc,
+};
+
+use parser::types::{EnumDecl, Field, Function, SharedDecl, StructDecl, Type};
+
+use crate::{environment::Environment, native::NativeFunction};
+
+/// Dynamically typed runtime value produced by evaluating a node.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Number(f64),
+    String(String),
+    Char(char),
+    Boolean(bool),
+    Function(Rc<FunctionValue>),
+    Native(Rc<NativeFunction>),
+    Struct(Rc<StructType>),
+    Shared(Rc<SharedType>),
+    Instance(Rc<RefCell<Instance>>),
+    /// Half open `start..end` range of numbers.
+    Range(f64, f64),
+    Enum(Rc<EnumType>),
+    /// A tuple variant before it is called, `Shape::Circle`.
+    Constructor(Rc<EnumType>, usize),
+    Variant(Rc<VariantValue>),
+    Array(Rc<RefCell<Vec<Value>>>),
+    /// Entries keep their insertion order.
+    Map(Rc<RefCell<Vec<(Value, Value)>>>),
+    Tuple(Rc<Vec<Value>>),
+}
+
+/// A function declaration together with the scope it was declared in.
+pub struct FunctionValue {
+    pub declaration: Rc<Function>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl FunctionValue {
+    /// Name used in messages, closures have none of their own.
+    pub fn name(&self) -> &str {
+        match self.declaration.name.is_empty() {
+            true => "closure",
+            false => &self.declaration.name,
+        }
+    }
+}
+
+impl Debug for FunctionValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // the closure may hold this very function, so it is not printed
+        write!(f, "FunctionValue({})", self.name())
+    }
+}
+
+/// A field of a struct type. Its default is evaluated in `scope`, where the
+/// struct, interface or impl giving the default was declared.
+#[derive(Clone)]
+pub struct FieldType {
+    pub field: Field,
+    pub scope: Rc<RefCell<Environment>>,
+}
+
+/// A declared struct, callable through paths like `Sheep::new`. Fields and
+/// methods grow when a `shared` interface is implemented on the struct.
+pub struct StructType {
+    pub declaration: Rc<StructDecl>,
+    pub fields: RefCell<Vec<FieldType>>,
+    pub methods: RefCell<Vec<Rc<FunctionValue>>>,
+    pub implements: RefCell<Vec<Rc<SharedType>>>,
+}
+
+impl StructType {
+    pub fn name(&self) -> &str {
+        &self.declaration.name
+    }
+
+    pub fn method(&self, name: &str) -> Option<Rc<FunctionValue>> {
+        self.methods
+            .borrow()
+            .iter()
+            .find(|m| m.declaration.name == name)
+            .cloned()
+    }
+
+    pub fn has_member(&self, name: &str) -> bool {
+        self.fields.borrow().iter().any(|f| f.field.name == name) || self.method(name).is_some()
+    }
+
+    /// The annotation of field `name` when it rules out `value`. Optional
+    /// fields also hold `nil`.
+    pub fn rejects(&self, name: &str, value: &Value) -> Option<Type> {
+        let fields = self.fields.borrow();
+        let field = &fields.iter().find(|f| f.field.name == name)?.field;
+        match &field.annotation {
+            Some(_) if field.optional && matches!(value, Value::Nil) => None,
+            Some(t) if !value.is_a(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Debug for StructType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "StructType({})", self.name())
+    }
+}
+
+/// A declared `shared` interface. Default methods close over the scope the
+/// interface was declared in.
+pub struct SharedType {
+    pub declaration: Rc<SharedDecl>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl SharedType {
+    pub fn name(&self) -> &str {
+        &self.declaration.name
+    }
+
+    pub fn has_member(&self, name: &str) -> bool {
+        self.declaration.fields.iter().any(|f| f.name == name)
+            || self.declaration.methods.iter().any(|m| m.name == name)
+    }
+}
+
+impl Debug for SharedType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "SharedType({})", self.name())
+    }
+}
+
+/// A declared enum, variants are reached through paths like `Shape::Empty`.
+pub struct EnumType {
+    pub declaration: Rc<EnumDecl>,
+}
+
+impl EnumType {
+    pub fn name(&self) -> &str {
+        &self.declaration.name
+    }
+
+    /// Tag of the variant called `name`.
+    pub fn tag(&self, name: &str) -> Option<usize> {
+        self.declaration
+            .variants
+            .iter()
+            .position(|v| v.name == name)
+    }
+
+    pub fn variant_name(&self, tag: usize) -> &str {
+        &self.declaration.variants[tag].name
+    }
+}
+
+impl Debug for EnumType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "EnumType({})", self.name())
+    }
+}
+
+/// An enum value. The tag indexes the declaration's variants, so values
+/// always know which variant they are.
+#[derive(Debug)]
+pub struct VariantValue {
+    pub enum_type: Rc<EnumType>,
+    pub tag: usize,
+    pub data: VariantData,
+}
+
+impl VariantValue {
+    pub fn name(&self) -> &str {
+        self.enum_type.variant_name(self.tag)
+    }
+
+    /// Field of a struct like variant.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match &self.data {
+            VariantData::Struct(fields) => fields.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VariantData {
+    Unit,
+    Tuple(Vec<Value>),
+    Struct(Vec<(String, Value)>),
+}
+
+/// A struct value. Fields are kept in declaration order for printing.
+#[derive(Debug)]
+pub struct Instance {
+    pub struct_type: Rc<StructType>,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Instance {
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+impl Value {
+    /// Only `nil` and `false` are falsy, every other value is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Boolean(false))
+    }
+
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Nil => "nil".to_string(),
+            Value::Number(_) => "number".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Char(_) => "char".to_string(),
+            Value::Boolean(_) => "bool".to_string(),
+            Value::Function(_) | Value::Native(_) => "function".to_string(),
+            Value::Struct(s) => format!("struct {}", s.name()),
+            Value::Shared(s) => format!("shared {}", s.name()),
+            Value::Instance(i) => i.borrow().struct_type.name().to_string(),
+            Value::Range(..) => "range".to_string(),
+            Value::Enum(e) => format!("enum {}", e.name()),
+            Value::Constructor(..) => "function".to_string(),
+            Value::Variant(v) => v.enum_type.name().to_string(),
+            Value::Array(_) => "array".to_string(),
+            Value::Map(_) => "map".to_string(),
+            Value::Tuple(_) => "tuple".to_string(),
+        }
+    }
+
+    /// The payload of a built-in `Result`, `Ok(value)` or `Err(error)`.
+    /// `None` for every other value.
+    pub fn as_result(&self) -> Option<Result<&Value, &Value>> {
+        let Value::Variant(v) = self else {
+            return None;
+        };
+        let VariantData::Tuple(values) = &v.data else {
+            return None;
+        };
+        if v.enum_type.name() != "Result" || values.len() != 1 {
+            return None;
+        }
+        match v.name() {
+            "Ok" => Some(Ok(&values[0])),
+            "Err" => Some(Err(&values[0])),
+            _ => None,
+        }
+    }
+
+    /// Whether the value fits an annotated type. Instances fit their struct
+    /// and every interface implemented on it, `this` fits any instance.
+    pub fn is_a(&self, t: &Type) -> bool {
+        match (t, self) {
+            (Type::String, Value::String(_))
+            | (Type::Number, Value::Number(_))
+            | (Type::Char, Value::Char(_))
+            | (Type::Bool, Value::Boolean(_))
+            | (Type::Nil, Value::Nil)
+            | (Type::This, Value::Instance(_)) => true,
+            (Type::Named(name), Value::Instance(i)) => {
+                let struct_type = &i.borrow().struct_type;
+                struct_type.name() == name
+                    || struct_type
+                        .implements
+                        .borrow()
+                        .iter()
+                        .any(|s| s.name() == name)
+            }
+            (Type::Named(name), Value::Variant(v)) => v.enum_type.name() == name,
+            _ => false,
+        }
+    }
+
+    /// Formats the value as it appears nested inside another value, where
+    /// strings and chars keep their quotes.
+    pub fn repr(&self) -> String {
+        self.nested(&mut vec![])
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        equal(self, other, &mut vec![])
+    }
+}
+
+/// Compares two values. `seen` holds the pairs of collections compared
+/// further up, meeting one of them again means both sides loop back the
+/// same way, which is taken as equal.
+fn equal(a: &Value, b: &Value, seen: &mut Vec<(*const (), *const ())>) -> bool {
+    match (a, b) {
+        (Value::Nil, Value::Nil) => true,
+        (Value::Number(a), Value::Number(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Char(a), Value::Char(b)) => a == b,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::Range(a, b), Value::Range(c, d)) => a == c && b == d,
+        (Value::Enum(a), Value::Enum(b)) => Rc::ptr_eq(a, b),
+        (Value::Constructor(a, x), Value::Constructor(b, y)) => Rc::ptr_eq(a, b) && x == y,
+        // collections compare by their contents
+        (Value::Array(a), Value::Array(b)) => {
+            nested(a, b, seen, |seen| all_equal(&a.borrow(), &b.borrow(), seen))
+        }
+        (Value::Tuple(a), Value::Tuple(b)) => nested(a, b, seen, |seen| all_equal(a, b, seen)),
+        (Value::Map(a), Value::Map(b)) => nested(a, b, seen, |seen| {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter().all(|(k, v)| {
+                    b.iter()
+                        .any(|(bk, bv)| equal(k, bk, seen) && equal(v, bv, seen))
+                })
+        }),
+        // variants compare by tag and payload
+        (Value::Variant(a), Value::Variant(b)) => {
+            Rc::ptr_eq(&a.enum_type, &b.enum_type)
+                && a.tag == b.tag
+                && match (&a.data, &b.data) {
+                    (VariantData::Unit, VariantData::Unit) => true,
+                    (VariantData::Tuple(a), VariantData::Tuple(b)) => all_equal(a, b, seen),
+                    (VariantData::Struct(a), VariantData::Struct(b)) => fields_equal(a, b, seen),
+                    _ => false,
+                }
+        }
+        // functions are only equal to themselves
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        (Value::Native(a), Value::Native(b)) => Rc::ptr_eq(&a.func, &b.func),
+        (Value::Struct(a), Value::Struct(b)) => Rc::ptr_eq(a, b),
+        (Value::Shared(a), Value::Shared(b)) => Rc::ptr_eq(a, b),
+        // instances compare by their data
+        (Value::Instance(a), Value::Instance(b)) => nested(a, b, seen, |seen| {
+            let (a, b) = (a.borrow(), b.borrow());
+            Rc::ptr_eq(&a.struct_type, &b.struct_type) && fields_equal(&a.fields, &b.fields, seen)
+        }),
+        _ => false,
+    }
+}
+
+/// Compares the contents of two collections with `compare`, unless they are
+/// the same collection or already being compared.
+fn nested<T>(
+    a: &Rc<T>,
+    b: &Rc<T>,
+    seen: &mut Vec<(*const (), *const ())>,
+    compare: impl FnOnce(&mut Vec<(*const (), *const ())>) -> bool,
+) -> bool {
+    let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+    if Rc::ptr_eq(a, b) || seen.contains(&pair) {
+        return true;
+    }
+    seen.push(pair);
+    let equal = compare(seen);
+    seen.pop();
+    equal
+}
+
+fn all_equal(a: &[Value], b: &[Value], seen: &mut Vec<(*const (), *const ())>) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b, seen))
+}
+
+fn fields_equal(
+    a: &[(String, Value)],
+    b: &[(String, Value)],
+    seen: &mut Vec<(*const (), *const ())>,
+) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|((n, a), (m, b))| n == m && equal(a, b, seen))
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(&mut vec![]))
+    }
+}
+
+impl Value {
+    /// The printed form of the value. `seen` holds the collections being
+    /// printed further up, one that contains itself prints as `[...]` the
+    /// second time round.
+    fn render(&self, seen: &mut Vec<*const ()>) -> String {
+        match self {
+            Value::Nil => "nil".to_string(),
+            Value::Number(n) => {
+                // print whole numbers without a trailing `.0`
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    format!("{}", *n as i64)
+                } else {
+                    format!("{}", n)
+                }
+            }
+            Value::String(s) => s.clone(),
+            Value::Char(c) => c.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            Value::Function(func) if func.declaration.name.is_empty() => "<closure>".to_string(),
+            Value::Function(func) => format!("<fn {}>", func.declaration.name),
+            Value::Native(native) => format!("<native fn {}>", native.name),
+            Value::Struct(s) => format!("<struct {}>", s.name()),
+            Value::Shared(s) => format!("<shared {}>", s.name()),
+            Value::Range(start, end) => {
+                format!("{}..{}", Value::Number(*start), Value::Number(*end))
+            }
+            Value::Array(items) => visit(items, seen, "[...]", |seen| {
+                let items: Vec<String> = items.borrow().iter().map(|v| v.nested(seen)).collect();
+                format!("[{}]", items.join(", "))
+            }),
+            Value::Map(entries) => visit(entries, seen, "[...]", |seen| {
+                let entries = entries.borrow();
+                if entries.is_empty() {
+                    return "[:]".to_string();
+                }
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.nested(seen), v.nested(seen)))
+                    .collect();
+                format!("[{}]", entries.join(", "))
+            }),
+            Value::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.nested(seen)).collect();
+                match items.len() {
+                    1 => format!("({},)", items[0]),
+                    _ => format!("({})", items.join(", ")),
+                }
+            }
+            Value::Enum(e) => format!("<enum {}>", e.name()),
+            Value::Constructor(e, tag) => format!("<fn {}::{}>", e.name(), e.variant_name(*tag)),
+            Value::Variant(v) => {
+                let name = format!("{}::{}", v.enum_type.name(), v.name());
+                match &v.data {
+                    VariantData::Unit => name,
+                    VariantData::Tuple(values) => {
+                        let values: Vec<String> = values.iter().map(|v| v.nested(seen)).collect();
+                        format!("{name}({})", values.join(", "))
+                    }
+                    VariantData::Struct(fields) => {
+                        format!("{name} {{ {} }}", render_fields(fields, seen))
+                    }
+                }
+            }
+            Value::Instance(i) => {
+                let name = i.borrow().struct_type.name().to_string();
+                visit(i, seen, &format!("{name} {{...}}"), |seen| {
+                    let instance = i.borrow();
+                    match instance.fields.is_empty() {
+                        true => format!("{name} {{}}"),
+                        false => format!("{name} {{ {} }}", render_fields(&instance.fields, seen)),
+                    }
+                })
+            }
+        }
+    }
+
+    /// Like `repr`, for a value printed inside another one.
+    fn nested(&self, seen: &mut Vec<*const ()>) -> String {
+        match self {
+            Value::String(s) => format!("{:?}", s),
+            Value::Char(c) => format!("{:?}", c),
+            v => v.render(seen),
+        }
+    }
+}
+
+/// Prints a collection with `render`, or as `cycle` when it is already being
+/// printed further up.
+fn visit<T>(
+    value: &Rc<T>,
+    seen: &mut Vec<*const ()>,
+    cycle: &str,
+    render: impl FnOnce(&mut Vec<*const ()>) -> String,
+) -> String {
+    let pointer = Rc::as_ptr(value) as *const ();
+    if seen.contains(&pointer) {
+        return cycle.to_string();
+    }
+    seen.push(pointer);
+    let text = render(seen);
+    seen.pop();
+    text
+}
+
+fn render_fields(fields: &[(String, Value)], seen: &mut Vec<*const ()>) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value.nested(seen)))
+        .collect();
+    fields.join(", ")
+}
@@ -0,0 +1,524 @@
+//! Evaluates small programs and checks the value of their last statement.
+
+use std::error::Error;
+
+use error::types::{CompilerError, ErrorTypes};
+use interpreter::{interpreter::Interpreter, value::Value};
+use lexer::scanner::Scanner;
+use parser::parser::Parser;
+
+/// Runs `source`, failing with the first error it reports.
+fn run(source: &str) -> Result<Value, CompilerError> {
+    let first = |mut errors: Vec<CompilerError>| errors.remove(0);
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens().map_err(first)?;
+    let program = Parser::new(&scanner.tokens)
+        .parse_program()
+        .map_err(first)?;
+    Interpreter::new().interpret(&program)
+}
+
+/// The value of the last statement of `source`, or the message of the
+/// error it failed with.
+fn eval(source: &str) -> String {
+    match run(source) {
+        Ok(value) => value.to_string(),
+        Err(error) => error.message.clone(),
+    }
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(eval("1 + 2 * 3"), "7");
+    assert_eq!(eval("(1 + 2) * 3"), "9");
+    assert_eq!(eval("10 - 4 - 3"), "3");
+    assert_eq!(eval("1 / 4"), "0.25");
+    assert_eq!(eval("-(2 + 3)"), "-5");
+}
+
+#[test]
+fn comparison_and_logic() {
+    assert_eq!(eval("1 < 2 && 2 <= 2"), "true");
+    assert_eq!(eval("1 == 2 || 3 != 3"), "false");
+    assert_eq!(eval("!true == false"), "true");
+}
+
+#[test]
+fn strings_concatenate_with_any_value() {
+    assert_eq!(eval("\"ion\" + \"ic\""), "ionic");
+    assert_eq!(eval("\"n = \" + 3"), "n = 3");
+}
+
+#[test]
+fn only_nil_and_false_are_falsy() {
+    assert_eq!(eval("!0"), "false");
+    assert_eq!(eval("!\"\""), "false");
+    assert_eq!(eval("!false"), "true");
+}
+
+#[test]
+fn declarations_bind_and_shadow() {
+    assert_eq!(eval("let a = 6; let b = a * 2; b"), "12");
+    assert_eq!(eval("let a = 1; let a = \"shadowed\"; a"), "shadowed");
+}
+
+#[test]
+fn runtime_errors() {
+    assert_eq!(eval("missing + 1"), "Undefined variable 'missing'");
+    assert_eq!(eval("-\"text\""), "Cannot negate a value of type string");
+    assert_eq!(
+        eval("1 - \"a\""),
+        "Unsupported operand types for -: number and string"
+    );
+}
+
+#[test]
+fn errors_are_returned_to_the_caller() {
+    let kind = |source| run(source).unwrap_err().error_type.clone();
+    assert_eq!(kind("let s = 'ab';"), ErrorTypes::TokenizationError);
+    assert_eq!(kind("let x = ;"), ErrorTypes::ParsingError);
+    assert_eq!(kind("missing;"), ErrorTypes::RuntimeError);
+}
+
+#[test]
+fn compiler_errors_are_std_errors() {
+    let error: Box<dyn Error> = Box::new(run("let x = ;").unwrap_err());
+    assert!(error.to_string().contains("ParsingError"));
+}
+
+#[test]
+fn functions_return_values() {
+    assert_eq!(eval("fn add(a, b) { return a + b; } add(2, 3)"), "5");
+    assert_eq!(eval("fn square(x) { x * x } square(square(2))"), "16");
+    assert_eq!(eval("fn nothing() {} nothing()"), "nil");
+    assert_eq!(eval("fn early() { return 1; 2 } early()"), "1");
+}
+
+#[test]
+fn functions_see_globals_but_not_each_others_locals() {
+    assert_eq!(
+        eval("let g = \"hi \"; fn greet(n) { g + n } greet(1)"),
+        "hi 1"
+    );
+    assert_eq!(
+        eval("fn f(x) { let y = x; y } f(1); y"),
+        "Undefined variable 'y'"
+    );
+}
+
+#[test]
+fn calls_check_their_arguments() {
+    assert_eq!(
+        eval("fn add(a, b) { a + b } add(1)"),
+        "Function 'add' expects 2 argument(s) but got 1"
+    );
+    assert_eq!(eval("let n = 1; n()"), "Cannot call a value of type number");
+}
+
+const POINT: &str = "
+struct Point {
+    x: number;
+    y: number;
+    fn new(x, y) => this {
+        Point { x, y }
+    }
+    fn sum() => number {
+        this.x + this.y
+    }
+    fn scaled(k) {
+        Point { x: x * k, y: y * k }
+    }
+}
+struct Counter {
+    n: number = 5;
+    fn get() {
+        n
+    }
+}
+";
+
+fn eval_with_point(source: &str) -> String {
+    eval(&format!("{POINT}{source}"))
+}
+
+#[test]
+fn struct_literals_and_fields() {
+    assert_eq!(
+        eval_with_point("Point { x: 1, y: 2 }"),
+        "Point { x: 1, y: 2 }"
+    );
+    assert_eq!(eval_with_point("let x = 3; Point { x, y: 4 }.x"), "3");
+    assert_eq!(eval_with_point("Counter {}.n + Counter { n: 1 }.n"), "6");
+    assert_eq!(
+        eval_with_point("Point { x: 1, y: 2 } == Point::new(1, 2)"),
+        "true"
+    );
+}
+
+#[test]
+fn methods_see_their_instance() {
+    assert_eq!(eval_with_point("Point::new(3, 4).sum()"), "7");
+    assert_eq!(
+        eval_with_point("Point::new(1, 2).scaled(2)"),
+        "Point { x: 2, y: 4 }"
+    );
+    assert_eq!(
+        eval_with_point("let c = Counter {}; let get = c.get; get()"),
+        "5"
+    );
+}
+
+#[test]
+fn struct_errors() {
+    assert_eq!(
+        eval_with_point("Point { x: 1, y: 2 }.z"),
+        "'Point' has no field or method 'z'"
+    );
+    assert_eq!(
+        eval_with_point("Point { x: 1 }"),
+        "Missing field 'y' in 'Point' literal"
+    );
+    assert_eq!(eval("Nope {}"), "Undefined struct 'Nope'");
+}
+
+const ANIMALS: &str = "
+shared Named {
+    name?: string = \"No Name\";
+    fn greet() => string {
+        \"I am \" + name
+    }
+    fn sound() => string;
+}
+shared Aged {
+    age: number = 1;
+}
+struct Wolf {}
+struct Sheep {
+    wool: number;
+}
+impl shared Named on Wolf {
+    fn sound() => string {
+        \"awoooo\"
+    }
+}
+impl shared Named on Sheep {
+    name = \"Dolly\";
+    fn sound() => string {
+        \"bahhh\"
+    }
+    fn greet() => string {
+        name + \" the sheep\"
+    }
+}
+impl shared Aged on Sheep {}
+";
+
+fn eval_with_animals(source: &str) -> String {
+    eval(&format!("{ANIMALS}{source}"))
+}
+
+#[test]
+fn shared_members_and_defaults() {
+    assert_eq!(eval_with_animals("Wolf {}.name"), "No Name");
+    assert_eq!(
+        eval_with_animals("Wolf { name: \"Jake\" }.greet()"),
+        "I am Jake"
+    );
+    assert_eq!(eval_with_animals("Wolf {}.sound()"), "awoooo");
+    assert_eq!(eval_with_animals("Sheep { wool: 3 }.age"), "1");
+    assert_eq!(eval_with_animals("Sheep { wool: 3, age: 4 }.age"), "4");
+}
+
+#[test]
+fn impls_override_defaults_and_methods() {
+    assert_eq!(eval_with_animals("Sheep { wool: 1 }.name"), "Dolly");
+    assert_eq!(
+        eval_with_animals("Sheep { wool: 1 }.greet()"),
+        "Dolly the sheep"
+    );
+}
+
+#[test]
+fn if_else_is_an_expression() {
+    let sign = "fn sign(n) { if n < 0 { \"-\" } else if n == 0 { \"0\" } else { \"+\" } }";
+    assert_eq!(eval(&format!("{sign} sign(-1) + sign(0) + sign(2)")), "-0+");
+    assert_eq!(eval("if 0 { \"truthy\" } else { \"falsy\" }"), "truthy");
+    assert_eq!(eval("if nil { 1 }"), "nil");
+}
+
+#[test]
+fn loops_break_continue_and_return() {
+    let find = "fn find() { for i in 0..10 { if i < 3 { continue; } return i; } }";
+    assert_eq!(eval(&format!("{find} find()")), "3");
+    assert_eq!(
+        eval("fn f() { while true { break; } \"done\" } f()"),
+        "done"
+    );
+    assert_eq!(eval("fn f() { while false { return 1; } 2 } f()"), "2");
+}
+
+#[test]
+fn blocks_open_a_scope() {
+    assert_eq!(eval("let x = \"outer\"; { let x = \"inner\"; } x"), "outer");
+    assert_eq!(
+        eval("for i in 0..2 { let y = i; } y"),
+        "Undefined variable 'y'"
+    );
+    assert_eq!(eval("for i in 0..2 {} i"), "Undefined variable 'i'");
+}
+
+const SIZE: &str = "
+fn size(n) {
+    match n {
+        0 => \"none\",
+        1..10 => \"few\",
+        m if m < 0 => \"negative \" + m,
+        _ => \"many\",
+    }
+}
+";
+
+#[test]
+fn match_literals_ranges_and_guards() {
+    assert_eq!(eval(&format!("{SIZE} size(0)")), "none");
+    assert_eq!(eval(&format!("{SIZE} size(9)")), "few");
+    assert_eq!(eval(&format!("{SIZE} size(10)")), "many");
+    assert_eq!(eval(&format!("{SIZE} size(-2)")), "negative -2");
+    assert_eq!(
+        eval("match \"ion\" { \"rust\" => 1, \"ion\" => 2, _ => 3 }"),
+        "2"
+    );
+}
+
+const WHERE: &str = "
+fn where_is(p) {
+    match p {
+        Point { x: 0, y: 0 } => \"origin\",
+        Point { x: 0, y } => \"y \" + y,
+        _ => \"elsewhere\",
+    }
+}
+";
+
+#[test]
+fn match_struct_patterns_bind_fields() {
+    let at = |point: &str| eval_with_point(&format!("{WHERE} where_is({point})"));
+    assert_eq!(at("Point { x: 0, y: 0 }"), "origin");
+    assert_eq!(at("Point { x: 0, y: 5 }"), "y 5");
+    assert_eq!(at("Point { x: 1, y: 1 }"), "elsewhere");
+}
+
+#[test]
+fn match_without_a_matching_arm_fails() {
+    assert_eq!(eval("match 5 { 0 => 1 }"), "No match arm matches 5");
+}
+
+const SHAPE: &str = "
+enum Shape {
+    Empty,
+    Circle(number),
+    Pair(_, number),
+    Rect { w: number, h: number },
+}
+fn area(s) {
+    match s {
+        Shape::Empty => 0,
+        Shape::Circle(r) => r * r * 3,
+        Shape::Pair(a, b) => a * b,
+        Shape::Rect { w, h } => w * h,
+    }
+}
+";
+
+fn eval_with_shape(source: &str) -> String {
+    eval(&format!("{SHAPE}{source}"))
+}
+
+#[test]
+fn enum_variants_keep_their_tag() {
+    assert_eq!(eval_with_shape("Shape::Empty"), "Shape::Empty");
+    assert_eq!(eval_with_shape("Shape::Pair(2, 5)"), "Shape::Pair(2, 5)");
+    assert_eq!(
+        eval_with_shape("Shape::Rect { w: 2, h: 3 }"),
+        "Shape::Rect { w: 2, h: 3 }"
+    );
+    assert_eq!(
+        eval_with_shape("Shape::Circle(1) == Shape::Circle(1)"),
+        "true"
+    );
+    assert_eq!(
+        eval_with_shape("Shape::Circle(1) == Shape::Circle(2)"),
+        "false"
+    );
+    assert_eq!(eval_with_shape("Shape::Empty == Shape::Circle(0)"), "false");
+}
+
+#[test]
+fn match_on_enum_variants() {
+    assert_eq!(eval_with_shape("area(Shape::Empty)"), "0");
+    assert_eq!(eval_with_shape("area(Shape::Circle(2))"), "12");
+    assert_eq!(eval_with_shape("area(Shape::Pair(2, 5))"), "10");
+    assert_eq!(eval_with_shape("area(Shape::Rect { w: 2, h: 3 })"), "6");
+}
+
+#[test]
+fn variants_are_built_with_their_payload() {
+    assert_eq!(
+        eval_with_shape("Shape::Circle(1, 2)"),
+        "Variant 'Shape::Circle' expects 1 value(s) but got 2"
+    );
+    assert_eq!(
+        eval_with_shape("Shape::Square"),
+        "Enum 'Shape' has no variant 'Square'"
+    );
+}
+
+#[test]
+fn closures_are_values() {
+    assert_eq!(eval("let double = |x| x * 2; double(4)"), "8");
+    assert_eq!(eval("let seven = || 7; seven()"), "7");
+    assert_eq!(eval("let add = fn(a, b) { a + b }; add(1, 2)"), "3");
+    assert_eq!(eval("let inc = fn(x) => x + 1; inc(9)"), "10");
+    assert_eq!(eval("(|x| x)(5)"), "5");
+}
+
+#[test]
+fn functions_are_passed_and_returned() {
+    let source = "fn twice(f, x) { f(f(x)) } fn adder(n) { |x| x + n }";
+    assert_eq!(eval(&format!("{source} twice(adder(10), 1)")), "21");
+    assert_eq!(eval(&format!("{source} let add = adder; add(2)(3)")), "5");
+}
+
+#[test]
+fn move_captures_current_values() {
+    let source = "let label = \"before\"; let by_ref = || label; let by_value = move || label;";
+    assert_eq!(
+        eval(&format!(
+            "{source} let label = \"after\"; by_ref() + \" \" + by_value()"
+        )),
+        "after before"
+    );
+}
+
+#[test]
+fn return_leaves_the_closure() {
+    let source =
+        "fn f() { let check = |x| { if x > 10 { return \"big\"; } \"small\" }; check(20) + \"!\" }";
+    assert_eq!(eval(&format!("{source} f()")), "big!");
+}
+
+#[test]
+fn pipes_call_with_the_value_first() {
+    let source = "fn double(x) { x * 2 } fn add(a, b) { a + b }";
+    assert_eq!(eval(&format!("{source} 3 -> double")), "6");
+    assert_eq!(eval(&format!("{source} 3 -> add(4) -> double")), "14");
+    assert_eq!(eval(&format!("{source} 1 + 2 -> double == 6")), "true");
+    assert_eq!(eval("5 -> 6"), "Cannot call a value of type number");
+}
+
+#[test]
+fn collection_literals() {
+    assert_eq!(eval("[1, \"two\", [3]]"), "[1, \"two\", [3]]");
+    assert_eq!(eval("[\"a\": 1, \"b\": 2]"), "[\"a\": 1, \"b\": 2]");
+    assert_eq!(eval("[:]"), "[:]");
+    assert_eq!(eval("(1, \"two\")"), "(1, \"two\")");
+    assert_eq!(eval("(1,)"), "(1,)");
+    assert_eq!(eval("()"), "()");
+}
+
+#[test]
+fn indexing_and_slicing() {
+    assert_eq!(eval("[1, 2, 3][2]"), "3");
+    assert_eq!(eval("[\"a\": 1, \"b\": 2][\"b\"]"), "2");
+    assert_eq!(eval("(1, \"two\")[1]"), "two");
+    assert_eq!(eval("[1, 2, 3, 4][1..3]"), "[2, 3]");
+    assert_eq!(eval("(1, 2, 3)[0..2]"), "(1, 2)");
+    assert_eq!(eval("\"ionic\"[0..3]"), "ion");
+}
+
+#[test]
+fn collections_compare_by_value() {
+    assert_eq!(eval("[1, [2, 3]] == [1, [2, 3]]"), "true");
+    assert_eq!(eval("(1, 2) == (2, 1)"), "false");
+    assert_eq!(eval("[\"k\": 1] == [\"k\": 1]"), "true");
+}
+
+#[test]
+fn for_walks_collections_and_strings() {
+    let find = "fn find(xs, wanted) { for x in xs { if x == wanted { return true; } } false }";
+    assert_eq!(eval(&format!("{find} find([1, 2], 2)")), "true");
+    assert_eq!(eval(&format!("{find} find((1, 2), 3)")), "false");
+    assert_eq!(eval(&format!("{find} find(\"ion\", 'o')")), "true");
+}
+
+#[test]
+fn bad_indexes_are_errors() {
+    assert_eq!(
+        eval("[1, 2][2]"),
+        "Index 2 out of bounds for array of length 2"
+    );
+    assert_eq!(eval("[\"a\": 1][\"b\"]"), "Key \"b\" not found in map");
+}
+
+#[test]
+fn prelude_functions_are_also_methods() {
+    assert_eq!(eval("len(\"ion\") + [1, 2].len()"), "5");
+    assert_eq!(eval("\" a,b \".trim().split(\",\")"), "[\"a\", \"b\"]");
+    assert_eq!(
+        eval("reduce(map([1, 2, 3], |x| x * 2), 0, |a, x| a + x)"),
+        "12"
+    );
+}
+
+#[test]
+fn natives_check_their_arguments() {
+    assert_eq!(
+        eval("len(1, 2)"),
+        "Function 'len' expects 1 argument(s) but got 2"
+    );
+    assert_eq!(eval("sqrt(\"four\")"), "Expected a number, found string");
+}
+
+#[test]
+fn hosts_register_natives() {
+    let mut scanner = Scanner::new("twice(21)");
+    scanner.scan_tokens().unwrap();
+    let program = Parser::new(&scanner.tokens).parse_program().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.register("twice", Some(1), |_, args, _| match &args[0] {
+        Value::Number(n) => Ok(Value::Number(n * 2.0)),
+        _ => Ok(Value::Nil),
+    });
+    assert_eq!(interpreter.interpret(&program).unwrap().to_string(), "42");
+}
+
+#[test]
+fn given_fields_are_evaluated_in_order_before_defaults() {
+    let source = "let log = []; fn f(x) { push(log, x); x } \
+                  struct S { a: number = f(1); b: number; c: number; } \
+                  S { c: f(3), b: f(2) }; log";
+    assert_eq!(eval(source), "[3, 2, 1]");
+}
+
+#[test]
+fn collections_can_contain_themselves() {
+    assert_eq!(eval("let xs = [1]; push(xs, xs); xs"), "[1, [...]]");
+    assert_eq!(eval("let xs = [1]; push(xs, xs); xs == xs"), "true");
+}
+
+#[test]
+fn fields_are_held_to_their_annotation() {
+    let point = "struct P { x: number; tag?: string; } let p = P { x: 1 };";
+    assert_eq!(
+        eval(&format!("{point} p.x = \"one\";")),
+        "Mismatched types: expected number for field 'x', found string"
+    );
+    assert_eq!(
+        eval(&format!("{point} P {{ x: nil }}")),
+        "Mismatched types: expected number for field 'x', found nil"
+    );
+    assert_eq!(
+        eval(&format!("{point} p.tag = nil; p.x = 2; p")),
+        "P { x: 2, tag: nil }"
+    );
+}
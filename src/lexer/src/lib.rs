@@ -1,3 +1,3 @@
 pub mod file_sys;
-pub mod types;
 pub mod scanner;
+pub mod types;
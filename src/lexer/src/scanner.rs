@@ -1,5 +1,5 @@
 use crate::types::{Token, TokenType};
-use error::types::{CompilerError, ErrorTypes};
+use error::types::{CompilerError, ErrorTypes, Span};
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -7,47 +7,71 @@ pub struct Scanner<'a> {
     source: Peekable<Chars<'a>>,
     source_len: usize,
     pub tokens: Vec<Token>,
+    diagnostics: Vec<CompilerError>,
     current: usize,
     line: usize,
     col: usize,
+    // byte offset, line and column where the token being scanned starts
+    start: usize,
+    start_line: usize,
+    start_col: usize,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(source: &'a String) -> Scanner<'a> {
+    pub fn new(source: &'a str) -> Scanner<'a> {
         Scanner {
             source_len: source.len().to_owned(),
             source: source.chars().peekable().to_owned(),
             tokens: vec![],
+            diagnostics: vec![],
             current: 0,
             line: 1,
             col: 1,
+            start: 0,
+            start_line: 1,
+            start_col: 1,
         }
     }
 
-    pub fn throw_scanner_error(
-        &self,
-        error_type: ErrorTypes,
-        message: &str,
-        col_adj: Option<usize>,
-    ) {
-        CompilerError::throw_new(
-            self.line,
-            self.col - col_adj.unwrap_or_else(|| 0),
-            message,
-            error_type,
-            None,
-        );
+    pub fn scanner_error(&self, error_type: ErrorTypes, message: &str) -> CompilerError {
+        CompilerError::at(self.token_span(), message.to_string(), error_type)
+    }
+
+    /// Records an error and lets the scanner carry on with the next token.
+    fn report(&mut self, error_type: ErrorTypes, message: &str) {
+        let error = self.scanner_error(error_type, message);
+        self.diagnostics.push(error);
     }
 
-    pub fn scan_tokens(&mut self) {
+    /// Span of the token scanned so far.
+    fn token_span(&self) -> Span {
+        Span::new(self.start, self.current, self.start_line, self.start_col)
+    }
+
+    fn mark_start(&mut self) {
+        self.start = self.current;
+        self.start_line = self.line;
+        self.start_col = self.col;
+    }
+
+    /// Scans the whole source. Every token is kept even when errors are found,
+    /// so the parser can still run and report its own diagnostics.
+    pub fn scan_tokens(&mut self) -> Result<(), Vec<CompilerError>> {
         while let Some(t) = self.next_token() {
             // push token t
             let _ = &self.tokens.push(t);
         }
 
+        self.mark_start();
         let _ = &self
             .tokens
-            .push(Token::new(self.line, self.col, TokenType::EOF));
+            .push(Token::new(self.token_span(), TokenType::EOF));
+
+        if self.diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
     }
 
     pub fn next_token(&mut self) -> Option<Token> {
@@ -60,10 +84,8 @@ impl<'a> Scanner<'a> {
 
         // eat spaces and special characters
         while !self.is_at_end() {
-            c = match self.advance_cursor() {
-                Some(ch) => ch,
-                None => return None,
-            };
+            self.mark_start();
+            c = self.advance_cursor()?;
 
             match c {
                 ' ' | '\t' | '\r' | '\n' => {
@@ -94,14 +116,14 @@ impl<'a> Scanner<'a> {
 
         token_type = self.parse_singleton(c);
 
-        if c.is_digit(10) {
+        if c.is_ascii_digit() {
             // check Number
             token_type = Some(self.parse_number(c));
         } else if c == '"' {
             // check String
-            token_type = Self::parse_string(self);
+            token_type = Some(Self::parse_string(self));
         } else if c == '\'' {
-            token_type = Self::parse_char(self);
+            token_type = Some(Self::parse_char(self));
         }
 
         if token_type.is_none() {
@@ -116,32 +138,24 @@ impl<'a> Scanner<'a> {
             }
         }
 
-        match token_type {
-            Some(t) => {
-                // create and push Token
-                Some(Token::new(self.line, self.col - t.to_literal().len(), t))
-            }
-            None => None,
-        }
+        // create and push Token
+        token_type.map(|t| Token::new(self.token_span(), t))
     }
 
     pub fn is_at_end(&self) -> bool {
-        &self.current >= &self.source_len
+        self.current >= self.source_len
     }
 
     fn advance_cursor(&mut self) -> Option<char> {
-        self.current += 1;
-        self.col += 1;
-
         let next = self.source.next();
-        match next {
-            Some(c) => {
-                if c == '\n' {
-                    self.line += 1;
-                    self.col = 1;
-                }
+        if let Some(c) = next {
+            self.current += c.len_utf8();
+            self.col += 1;
+
+            if c == '\n' {
+                self.line += 1;
+                self.col = 1;
             }
-            None => {}
         }
 
         next
@@ -149,17 +163,14 @@ impl<'a> Scanner<'a> {
 
     fn parse_double(&mut self, current: char) -> Option<TokenType> {
         let mut token_type: Option<TokenType> = None;
-        match self.source.peek() {
-            Some(nc) => {
-                let mut double = String::from(current);
-                double.push_str(nc.to_string().as_str());
-                token_type = TokenType::new(&double);
-
-                if token_type.is_some() {
-                    self.advance_cursor();
-                }
+        if let Some(nc) = self.source.peek() {
+            let mut double = String::from(current);
+            double.push_str(nc.to_string().as_str());
+            token_type = TokenType::new(&double);
+
+            if token_type.is_some() {
+                self.advance_cursor();
             }
-            None => {}
         }
 
         token_type
@@ -170,26 +181,34 @@ impl<'a> Scanner<'a> {
         let mut s = String::from(current_char);
 
         while let Some(c) = self.source.peek() {
-            let ch = c.clone();
+            let ch = *c;
             let dot: char;
 
             if ch == '.' {
                 dot = self.advance_cursor().unwrap();
-                match self.parse_double(dot) {
-                    Some(t) => {
-                        let _ = &self.tokens.push(Token::new(
-                            self.line,
-                            self.col - s.len(),
-                            TokenType::Number(s.clone()),
-                        ));
-
-                        return t;
-                    }
-                    None => {}
+                if let Some(t) = self.parse_double(dot) {
+                    // the number ends right before the two char operator
+                    let number_span = Span::new(
+                        self.start,
+                        self.current - 2,
+                        self.start_line,
+                        self.start_col,
+                    );
+                    let _ = &self
+                        .tokens
+                        .push(Token::new(number_span, TokenType::Number(s.clone())));
+
+                    self.start = self.current - 2;
+                    self.start_col = self.col - 2;
+                    return t;
                 }
+
+                // the dot was already consumed above
+                s.push(dot);
+                continue;
             }
 
-            if ch.is_digit(10) || ch == '.' {
+            if ch.is_ascii_digit() {
                 s.push_str(self.advance_cursor().unwrap().to_string().as_str());
             } else {
                 break;
@@ -199,7 +218,7 @@ impl<'a> Scanner<'a> {
         TokenType::Number(s.clone())
     }
 
-    fn parse_char(&mut self) -> Option<TokenType> {
+    fn parse_char(&mut self) -> TokenType {
         let mut s = String::new();
 
         while let Some(c) = self.source.peek() {
@@ -213,19 +232,18 @@ impl<'a> Scanner<'a> {
             }
         }
 
-        if s.len() == 1 {
-            Some(TokenType::Char(s.chars().nth(0).unwrap()))
-        } else {
-            self.throw_scanner_error(
-                ErrorTypes::TokenizationError,
-                "Unexpected character",
-                Some(s.len()),
-            );
-            None
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => TokenType::Char(c),
+            (first, _) => {
+                // keep a char token in place so parsing can continue
+                self.report(ErrorTypes::TokenizationError, "Unexpected character");
+                TokenType::Char(first.unwrap_or('\0'))
+            }
         }
     }
 
-    fn parse_string(&mut self) -> Option<TokenType> {
+    fn parse_string(&mut self) -> TokenType {
         let mut s = String::new();
 
         while let Some(c) = self.source.peek() {
@@ -233,13 +251,14 @@ impl<'a> Scanner<'a> {
                 // end of string
                 //consume end quote
                 let _ = self.advance_cursor();
-                break;
+                return TokenType::String(s.clone());
             } else {
                 s.push_str(self.advance_cursor().unwrap().to_string().as_str());
             }
         }
 
-        Some(TokenType::String(s.clone()))
+        self.report(ErrorTypes::TokenizationError, "Unterminated string");
+        TokenType::String(s.clone())
     }
 
     fn parse_token(&mut self, current_char: char) -> (Option<TokenType>, String) {
@@ -248,7 +267,7 @@ impl<'a> Scanner<'a> {
         let mut longest_match: usize = 0;
 
         while let Some(c) = self.source.peek() {
-            let next_char = c.clone();
+            let next_char = *c;
 
             let keyword_match = TokenType::new(&s);
             if let Some(t) = keyword_match {
@@ -285,10 +304,10 @@ impl<'a> Scanner<'a> {
     }
 
     fn is_double(c: &char) -> bool {
-        match c {
-            '!' | '=' | '>' | '<' | '.' | ':' | '|' | '&' | '-' => true,
-            _ => false,
-        }
+        matches!(
+            c,
+            '!' | '=' | '>' | '<' | '.' | ':' | '|' | '&' | '-' | '+' | '*' | '/' | '?'
+        )
     }
 
     fn parse_singleton(&mut self, c: char) -> Option<TokenType> {
@@ -309,18 +328,15 @@ impl<'a> Scanner<'a> {
     }
 
     fn stop_char(c: &char) -> bool {
-        match c {
-            '\n' | '\r' | ' ' | '.' => true,
-            _ => false,
-        }
+        matches!(c, '\n' | '\r' | ' ' | '.')
     }
 
     fn is_line_comment(&mut self, current_char: char) -> bool {
         let mut s = String::from(current_char);
-        let next_char = self.source.peek().unwrap_or_else(|| &'\0');
+        let next_char = self.source.peek().unwrap_or(&'\0');
         s.push_str(next_char.to_string().as_str());
 
-        s == String::from("//")
+        s == "//"
     }
 
     fn absorb_line_comment(&mut self) {
@@ -345,3 +361,69 @@ impl<'a> Scanner<'a> {
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::Scanner;
+    use crate::types::{IdentifierKind, OperatorKind, PunctuationKind, TokenType};
+
+    fn scan(source: &str) -> (Vec<TokenType>, usize) {
+        let mut scanner = Scanner::new(source);
+        let errors = scanner.scan_tokens().err().map_or(0, |e| e.len());
+        let tokens = scanner.tokens.into_iter().map(|t| t.token_type).collect();
+        (tokens, errors)
+    }
+
+    #[test]
+    fn scans_a_declaration() {
+        let (tokens, errors) = scan("let x = 1.5;");
+        assert_eq!(errors, 0);
+        assert_eq!(
+            tokens,
+            vec![
+                TokenType::Identifiers(IdentifierKind::LET),
+                TokenType::Name("x".to_string()),
+                TokenType::Operators(OperatorKind::EQUAL),
+                TokenType::Number("1.5".to_string()),
+                TokenType::Punctuation(PunctuationKind::SEMICOLON),
+                TokenType::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_number_followed_by_a_range_keeps_both() {
+        let (tokens, _) = scan("0..10");
+        assert_eq!(
+            tokens,
+            vec![
+                TokenType::Number("0".to_string()),
+                TokenType::Operators(OperatorKind::SPREAD),
+                TokenType::Number("10".to_string()),
+                TokenType::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        let (tokens, _) = scan("// a comment\nx");
+        assert_eq!(
+            tokens,
+            vec![TokenType::Name("x".to_string()), TokenType::EOF]
+        );
+    }
+
+    #[test]
+    fn keeps_scanning_after_a_bad_char_literal() {
+        let (tokens, errors) = scan("let a = 'xy'; let b = 'c';");
+        assert_eq!(errors, 1);
+        assert!(tokens.contains(&TokenType::Char('c')));
+        assert_eq!(tokens.last(), Some(&TokenType::EOF));
+    }
+
+    #[test]
+    fn reports_every_error_in_the_source() {
+        let (_, errors) = scan("'ab'; 'cd'; \"open");
+        assert_eq!(errors, 3);
+    }
+}
@@ -1,19 +1,23 @@
 use std::fmt::{self, Debug, Display, Formatter};
 
+pub use error::types::Span;
+
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
     pub line: usize,
     pub col: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(line: usize, col: usize, token_type: TokenType) -> Token {
+    pub fn new(span: Span, token_type: TokenType) -> Token {
         Token {
             literal: token_type.to_literal().clone(),
             token_type,
-            line,
-            col,
+            line: span.line,
+            col: span.col,
+            span,
         }
     }
 
@@ -32,6 +36,10 @@ impl Token {
     pub fn get_col(&self) -> &usize {
         &self.col
     }
+
+    pub fn get_span(&self) -> &Span {
+        &self.span
+    }
 }
 
 impl Display for Token {
@@ -60,9 +68,9 @@ impl TokenType {
         }
     }
 
-    pub fn new(token: &String) -> Option<TokenType> {
+    pub fn new(token: &str) -> Option<TokenType> {
         let t = match token.len() {
-            1 => match token.as_str() {
+            1 => match token {
                 "(" => TokenType::Punctuation(PunctuationKind::LPAREN),
                 ")" => TokenType::Punctuation(PunctuationKind::RPAREN),
                 "[" => TokenType::Punctuation(PunctuationKind::LBRACE),
@@ -85,7 +93,7 @@ impl TokenType {
                 "|" => TokenType::Operators(OperatorKind::BAR),
                 _ => return None,
             },
-            2 => match token.as_str() {
+            2 => match token {
                 "!=" => TokenType::Operators(OperatorKind::BangEqual),
                 "==" => TokenType::Operators(OperatorKind::EqualEqual),
                 ">=" => TokenType::Operators(OperatorKind::GreaterEqual),
@@ -94,37 +102,48 @@ impl TokenType {
                 "->" => TokenType::Operators(OperatorKind::SingleArrow),
                 ".." => TokenType::Operators(OperatorKind::SPREAD),
                 ":=" => TokenType::Operators(OperatorKind::ColonEqual),
+                "??" => TokenType::Operators(OperatorKind::QuestionQuestion),
+                "?." => TokenType::Punctuation(PunctuationKind::QuestionDot),
+                "+=" => TokenType::Operators(OperatorKind::PlusEqual),
+                "-=" => TokenType::Operators(OperatorKind::MinusEqual),
+                "*=" => TokenType::Operators(OperatorKind::StarEqual),
+                "/=" => TokenType::Operators(OperatorKind::SlashEqual),
+                "::" => TokenType::Punctuation(PunctuationKind::DoubleColon),
                 "&&" => TokenType::Operators(OperatorKind::AND),
                 "||" => TokenType::Operators(OperatorKind::OR),
                 "if" => TokenType::Identifiers(IdentifierKind::IF),
                 "on" => TokenType::Identifiers(IdentifierKind::ON),
+                "in" => TokenType::Identifiers(IdentifierKind::IN),
                 "fn" => TokenType::Identifiers(IdentifierKind::FN),
                 _ => return None,
             },
-            3 => match token.as_str() {
+            3 => match token {
                 "for" => TokenType::Identifiers(IdentifierKind::FOR),
                 "let" => TokenType::Identifiers(IdentifierKind::LET),
                 "nil" => TokenType::Identifiers(IdentifierKind::NIL),
+                "mut" => TokenType::Identifiers(IdentifierKind::MUT),
                 _ => return None,
             },
-            4 => match token.as_str() {
+            4 => match token {
                 "else" => TokenType::Identifiers(IdentifierKind::ELSE),
                 "this" => TokenType::Identifiers(IdentifierKind::THIS),
                 "enum" => TokenType::Identifiers(IdentifierKind::ENUM),
                 "impl" => TokenType::Identifiers(IdentifierKind::IMPL),
+                "move" => TokenType::Identifiers(IdentifierKind::MOVE),
                 "bool" => TokenType::Type(TypeKind::BOOL),
                 "char" => TokenType::Type(TypeKind::CHAR),
                 "true" => TokenType::Bool(true),
                 _ => return None,
             },
-            5 => match token.as_str() {
+            5 => match token {
                 "super" => TokenType::Identifiers(IdentifierKind::SUPER),
                 "while" => TokenType::Identifiers(IdentifierKind::WHILE),
                 "match" => TokenType::Identifiers(IdentifierKind::MATCH),
+                "break" => TokenType::Identifiers(IdentifierKind::BREAK),
                 "false" => TokenType::Bool(false),
                 _ => return None,
             },
-            6 => match token.as_str() {
+            6 => match token {
                 "struct" => TokenType::Identifiers(IdentifierKind::STRUCT),
                 "return" => TokenType::Identifiers(IdentifierKind::RETURN),
                 "shared" => TokenType::Identifiers(IdentifierKind::SHARED),
@@ -132,6 +151,10 @@ impl TokenType {
                 "string" => TokenType::Type(TypeKind::STRING),
                 _ => return None,
             },
+            8 => match token {
+                "continue" => TokenType::Identifiers(IdentifierKind::CONTINUE),
+                _ => return None,
+            },
             _ => return None,
         };
 
@@ -176,23 +199,28 @@ pub enum PunctuationKind {
     SEMICOLON,
     COLON,
     QUESTION,
+    DoubleColon,
+    QuestionDot,
 }
 
-impl PunctuationKind {
-    pub fn to_string(&self) -> String {
-        match &self {
-            PunctuationKind::LPAREN => String::from("("),
-            PunctuationKind::RPAREN => String::from(")"),
-            PunctuationKind::LBRACE => String::from("["),
-            PunctuationKind::RBRACE => String::from("]"),
-            PunctuationKind::LCURL => String::from("{"),
-            PunctuationKind::RCURL => String::from("}"),
-            PunctuationKind::COMMA => String::from(","),
-            PunctuationKind::DOT => String::from("."),
-            PunctuationKind::SEMICOLON => String::from(","),
-            PunctuationKind::COLON => String::from(":"),
-            PunctuationKind::QUESTION => String::from("?"),
-        }
+impl Display for PunctuationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match &self {
+            PunctuationKind::LPAREN => "(",
+            PunctuationKind::RPAREN => ")",
+            PunctuationKind::LBRACE => "[",
+            PunctuationKind::RBRACE => "]",
+            PunctuationKind::LCURL => "{",
+            PunctuationKind::RCURL => "}",
+            PunctuationKind::COMMA => ",",
+            PunctuationKind::DOT => ".",
+            PunctuationKind::SEMICOLON => ";",
+            PunctuationKind::COLON => ":",
+            PunctuationKind::QUESTION => "?",
+            PunctuationKind::DoubleColon => "::",
+            PunctuationKind::QuestionDot => "?.",
+        };
+        write!(f, "{}", s)
     }
 }
 
@@ -217,31 +245,42 @@ pub enum OperatorKind {
     OR,
     BAR,
     SingleArrow,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    QuestionQuestion,
 }
 
-impl OperatorKind {
-    pub fn to_string(&self) -> String {
-        match &self {
-            OperatorKind::MINUS => String::from("-"),
-            OperatorKind::PLUS => String::from("+"),
-            OperatorKind::SLASH => String::from("/"),
-            OperatorKind::STAR => String::from("*"),
-            OperatorKind::BANG => String::from("!"),
-            OperatorKind::EQUAL => String::from("="),
-            OperatorKind::GREATER => String::from(">"),
-            OperatorKind::LESS => String::from("<"),
-            OperatorKind::BangEqual => String::from("!="),
-            OperatorKind::EqualEqual => String::from("=="),
-            OperatorKind::GreaterEqual => String::from(">="),
-            OperatorKind::LessEqual => String::from("<="),
-            OperatorKind::ARROW => String::from("=>"),
-            OperatorKind::SPREAD => String::from(".."),
-            OperatorKind::ColonEqual => String::from(":="),
-            OperatorKind::AND => String::from("&&"),
-            OperatorKind::OR => String::from("||"),
-            OperatorKind::BAR => String::from("|"),
-            OperatorKind::SingleArrow => String::from("->"),
-        }
+impl Display for OperatorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match &self {
+            OperatorKind::MINUS => "-",
+            OperatorKind::PLUS => "+",
+            OperatorKind::SLASH => "/",
+            OperatorKind::STAR => "*",
+            OperatorKind::BANG => "!",
+            OperatorKind::EQUAL => "=",
+            OperatorKind::GREATER => ">",
+            OperatorKind::LESS => "<",
+            OperatorKind::BangEqual => "!=",
+            OperatorKind::EqualEqual => "==",
+            OperatorKind::GreaterEqual => ">=",
+            OperatorKind::LessEqual => "<=",
+            OperatorKind::ARROW => "=>",
+            OperatorKind::SPREAD => "..",
+            OperatorKind::ColonEqual => ":=",
+            OperatorKind::AND => "&&",
+            OperatorKind::OR => "||",
+            OperatorKind::BAR => "|",
+            OperatorKind::SingleArrow => "->",
+            OperatorKind::PlusEqual => "+=",
+            OperatorKind::MinusEqual => "-=",
+            OperatorKind::StarEqual => "*=",
+            OperatorKind::SlashEqual => "/=",
+            OperatorKind::QuestionQuestion => "??",
+        };
+        write!(f, "{}", s)
     }
 }
 
@@ -263,28 +302,39 @@ pub enum IdentifierKind {
     IMPL,
     SHARED,
     MATCH,
+    IN,
+    BREAK,
+    CONTINUE,
+    MOVE,
+    MUT,
 }
 
-impl IdentifierKind {
-    pub fn to_string(&self) -> String {
-        match &self {
-            IdentifierKind::IF => String::from("if"),
-            IdentifierKind::ON => String::from("on"),
-            IdentifierKind::FN => String::from("fn"),
-            IdentifierKind::FOR => String::from("for"),
-            IdentifierKind::LET => String::from("let"),
-            IdentifierKind::NIL => String::from("nil"),
-            IdentifierKind::ELSE => String::from("else"),
-            IdentifierKind::THIS => String::from("this"),
-            IdentifierKind::STRUCT => String::from("struct"),
-            IdentifierKind::SUPER => String::from("super"),
-            IdentifierKind::WHILE => String::from("while"),
-            IdentifierKind::RETURN => String::from("return"),
-            IdentifierKind::ENUM => String::from("enum"),
-            IdentifierKind::IMPL => String::from("impl"),
-            IdentifierKind::SHARED => String::from("shared"),
-            IdentifierKind::MATCH => String::from("match"),
-        }
+impl Display for IdentifierKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match &self {
+            IdentifierKind::IF => "if",
+            IdentifierKind::ON => "on",
+            IdentifierKind::FN => "fn",
+            IdentifierKind::FOR => "for",
+            IdentifierKind::LET => "let",
+            IdentifierKind::NIL => "nil",
+            IdentifierKind::ELSE => "else",
+            IdentifierKind::THIS => "this",
+            IdentifierKind::STRUCT => "struct",
+            IdentifierKind::SUPER => "super",
+            IdentifierKind::WHILE => "while",
+            IdentifierKind::RETURN => "return",
+            IdentifierKind::ENUM => "enum",
+            IdentifierKind::IMPL => "impl",
+            IdentifierKind::SHARED => "shared",
+            IdentifierKind::MATCH => "match",
+            IdentifierKind::IN => "in",
+            IdentifierKind::BREAK => "break",
+            IdentifierKind::CONTINUE => "continue",
+            IdentifierKind::MOVE => "move",
+            IdentifierKind::MUT => "mut",
+        };
+        write!(f, "{}", s)
     }
 }
 
@@ -296,13 +346,14 @@ pub enum TypeKind {
     BOOL,
 }
 
-impl TypeKind {
-    pub fn to_string(&self) -> String {
-        match &self {
-            TypeKind::STRING => String::from("string"),
-            TypeKind::NUMBER => String::from("number"),
-            TypeKind::CHAR => String::from("char"),
-            TypeKind::BOOL => String::from("bool"),
-        }
+impl Display for TypeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match &self {
+            TypeKind::STRING => "string",
+            TypeKind::NUMBER => "number",
+            TypeKind::CHAR => "char",
+            TypeKind::BOOL => "bool",
+        };
+        write!(f, "{}", s)
     }
 }
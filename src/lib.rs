@@ -0,0 +1,56 @@
+pub mod engine;
+
+use analyzer::{
+    conformance::check_conformance, declarations::Declarations,
+    exhaustiveness::check_exhaustiveness, typecheck::check_types,
+};
+use error::types::CompilerError;
+use lexer::scanner::Scanner;
+use parser::{parser::Parser, types::Program};
+
+pub use engine::Engine;
+
+/// Lexes and parses source code, collecting the diagnostics of both stages.
+pub fn parse(source: &str) -> Result<Program, Vec<CompilerError>> {
+    // lexical analysis
+    let mut scanner = Scanner::new(source);
+    let mut diagnostics = scanner.scan_tokens().err().unwrap_or_default();
+    let tokens = scanner.tokens;
+
+    // parsing
+    let mut parser = Parser::new(&tokens);
+    match parser.parse_program() {
+        Ok(program) if diagnostics.is_empty() => Ok(program),
+        Ok(_) => Err(diagnostics),
+        Err(mut errors) => {
+            diagnostics.append(&mut errors);
+            Err(diagnostics)
+        }
+    }
+}
+
+/// Runs the static passes over a parsed program. Succeeds with the warnings
+/// found, or fails with every diagnostic.
+pub fn check(program: &Program) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    check_after(program, &Declarations::new())
+}
+
+/// Like `check`, for a program continuing earlier ones that made
+/// `declarations`.
+pub fn check_after(
+    program: &Program,
+    declarations: &Declarations,
+) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    let mut diagnostics = check_exhaustiveness(program, declarations);
+    let warnings = diagnostics.len();
+    for pass in [check_conformance, check_types] {
+        if let Err(mut errors) = pass(program, declarations) {
+            diagnostics.append(&mut errors);
+        }
+    }
+
+    match diagnostics.len() == warnings {
+        true => Ok(diagnostics),
+        false => Err(diagnostics),
+    }
+}
@@ -1,28 +1,292 @@
+mod repl;
+
+use error::{
+    render::Renderer,
+    types::{CompilerError, ErrorTypes, Span},
+};
+use interpreter::value::Value;
+use ion::{check, parse, Engine};
 use lexer::{file_sys::file_to_string, scanner::Scanner};
-use parser::parser::Parser;
-use std::{env, process::exit};
+use parser::{format::format_program, json::program_to_json, parser::Parser, types::Program};
+use std::{
+    env,
+    io::{self, stderr, IsTerminal, Read},
+    process::exit,
+    thread,
+};
+use vm::vm::Vm;
+
+// both backends recurse on the host stack for every call, this leaves room
+// for their own call depth limit to be reached first
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// A stage over a source file. Succeeds with the warnings it found, or fails
+/// with every diagnostic.
+type Stage = fn(&str) -> Result<Vec<CompilerError>, Vec<CompilerError>>;
+
+const USAGE: &str = "\
+Usage: ion [command] [file] [options]
+
+Commands:
+    run <file>      run a program, the default when only a file is given
+    check <file>    lex, parse and check a program without running it
+    tokens <file>   print the tokens of a program
+    ast <file>      print the syntax tree of a program
+    fmt <file>      print a program in the standard format
+
+Without a command or file, ion starts a REPL. A file of `-`, or leaving it
+out after a command, reads the program from stdin.
+
+Options:
+    --json          print the syntax tree as JSON (ast)
+    --backend=vm    run on the bytecode VM instead of the tree-walker (run)
+    --no-color      plain diagnostics, also set by NO_COLOR
+    -h, --help      print this help
+
+Exit codes: 0 success, 64 bad usage, 65 invalid program, 66 unreadable
+file, 70 runtime error.";
 
 pub fn main() {
-    let args: Vec<String> = env::args().collect();
-    match args.len() {
-        2 => compile(&args[1]),
-        _ => {
-            println!("Error: No filename or path provided.");
-            exit(64);
+    match thread::Builder::new().stack_size(STACK_SIZE).spawn(cli) {
+        Ok(handle) => {
+            if handle.join().is_err() {
+                exit(70);
+            }
+        }
+        Err(_) => cli(),
+    }
+}
+
+fn cli() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return;
+    }
+
+    // colored diagnostics only make sense on a terminal
+    let no_color = take_flag(&mut args, "--no-color");
+    let color = !no_color && env::var_os("NO_COLOR").is_none() && stderr().is_terminal();
+    let json = take_flag(&mut args, "--json");
+    let run: Stage = match take_option(&mut args, "--backend").as_deref() {
+        None | Some("tree") => run,
+        Some("vm") => run_vm,
+        Some(backend) => return usage_error(&format!("unknown backend '{backend}'")),
+    };
+
+    if let Some(flag) = args.iter().find(|a| a.starts_with('-') && *a != "-") {
+        return usage_error(&format!("unknown option '{flag}'"));
+    }
+
+    let (stage, path) = match args.as_slice() {
+        [] => return repl::start(color),
+        [command, rest @ ..] => match (stage(command, json, run), rest) {
+            (Some(stage), []) => (stage, "-"),
+            (Some(stage), [path]) => (stage, path.as_str()),
+            (Some(_), _) => return usage_error("expected a single file"),
+            // `ion file.ion`
+            (None, []) => (run, command.as_str()),
+            (None, _) => return usage_error(&format!("unknown command '{command}'")),
+        },
+    };
+
+    let code = execute(path, color, stage);
+    if code != 0 {
+        exit(code);
+    }
+}
+
+fn stage(command: &str, json: bool, run: Stage) -> Option<Stage> {
+    let stage: Stage = match command {
+        "run" => run,
+        "check" => check_only,
+        "tokens" => tokens,
+        "ast" if json => ast_json,
+        "ast" => ast,
+        "fmt" => fmt,
+        _ => return None,
+    };
+    Some(stage)
+}
+
+/// Removes every `--option=value` from `args`, returning the last value.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let prefix = format!("{option}=");
+    let value = args
+        .iter()
+        .rev()
+        .find_map(|a| a.strip_prefix(&prefix))
+        .map(str::to_string);
+    args.retain(|a| !a.starts_with(&prefix));
+    value
+}
+
+/// Removes every `flag` from `args`, returning whether there was one.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|a| a != flag);
+    args.len() != len
+}
+
+fn usage_error(message: &str) {
+    eprintln!("error: {message}\nrun `ion --help` for usage");
+    exit(64);
+}
+
+/// Reads the program at `path`, or stdin for `-`, and runs `stage` over it,
+/// rendering any diagnostics. Returns the process exit code.
+fn execute(path: &str, color: bool, stage: Stage) -> i32 {
+    let (name, source) = match path {
+        "-" => ("<stdin>", read_stdin()),
+        _ => (path, file_to_string(path)),
+    };
+    let (source, result) = match source {
+        Ok(source) => {
+            let result = stage(&source);
+            (source, result)
+        }
+        Err(e) => (String::new(), Err(vec![e])),
+    };
+
+    let (diagnostics, failed) = match result {
+        Ok(warnings) => (warnings, false),
+        Err(diagnostics) => (diagnostics, true),
+    };
+
+    let renderer = Renderer::new(&source, name).with_color(color);
+    for e in diagnostics.iter() {
+        eprintln!("{}", renderer.render(e));
+    }
+
+    match failed {
+        true => diagnostics
+            .iter()
+            .find(|e| !e.is_warning())
+            .map_or(65, exit_code),
+        false => 0,
+    }
+}
+
+fn read_stdin() -> Result<String, CompilerError> {
+    let mut source = String::new();
+    match io::stdin().read_to_string(&mut source) {
+        Ok(_) => Ok(source),
+        Err(e) => Err(CompilerError::new(
+            0,
+            0,
+            format!("Could not read stdin: {e}"),
+            ErrorTypes::FileIOError,
+            None,
+        )),
+    }
+}
+
+/// Maps an error to a sysexits style exit code. This is the only place
+/// where an error decides how the process ends.
+fn exit_code(error: &CompilerError) -> i32 {
+    match error.error_type {
+        ErrorTypes::FileIOError => 66,
+        ErrorTypes::RuntimeError | ErrorTypes::Panic => 70,
+        ErrorTypes::GenericCompilerError
+        | ErrorTypes::TokenizationError
+        | ErrorTypes::ParsingError
+        | ErrorTypes::SemanticError => 65,
+    }
+}
+
+fn check_only(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    check(&parse(source)?)
+}
+
+fn tokens(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens()?;
+    match Parser::new(&scanner.tokens).print_tokens() {
+        Ok(()) => Ok(vec![]),
+        // the reader stopped early, as in `ion tokens file | head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(vec![]),
+        Err(e) => Err(vec![CompilerError::new(
+            0,
+            0,
+            format!("Could not write to stdout: {e}"),
+            ErrorTypes::FileIOError,
+            None,
+        )]),
+    }
+}
+
+fn ast(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    parse(source)?.print_nodes();
+    Ok(vec![])
+}
+
+fn ast_json(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    println!("{}", program_to_json(&parse(source)?));
+    Ok(vec![])
+}
+
+/// Prints the formatted program. The syntax tree has no comments, so a
+/// warning points at the first one that is left out.
+fn fmt(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    let program = parse(source)?;
+    print!("{}", format_program(&program));
+
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens()?;
+    let mut end = 0;
+    for token in scanner.tokens.iter() {
+        let gap = &source[end..token.span.start.max(end)];
+        if let Some(offset) = gap.find(|c: char| !c.is_whitespace()) {
+            let start = end + offset;
+            let (line, col) = position(source, start);
+            let comment = CompilerError::at(
+                Span::new(start, start + 2, line, col),
+                "Comments are not kept by `ion fmt`".to_string(),
+                ErrorTypes::GenericCompilerError,
+            )
+            .as_warning();
+            return Ok(vec![comment]);
         }
+        end = token.span.end.max(end);
     }
+    Ok(vec![])
 }
 
-fn compile(path: &String) {
-    // lexical analysis
+/// Line and column of a byte offset, both starting at 1.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let col = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, col)
+}
 
-    let file_path = file_to_string(path);
-    let mut scanner = Scanner::new(&file_path);
-    scanner.scan_tokens();
-    let mut tokens = scanner.tokens;
-    
-    // parsing
-    let mut parser = Parser::new(&mut tokens);
-    let ast = parser.parse_program();
-    ast.print_nodes();
+fn run(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    run_on(source, |program| {
+        let mut engine = Engine::new();
+        engine.set_stack_size(STACK_SIZE);
+        engine.execute(program)
+    })
+}
+
+fn run_vm(source: &str) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    run_on(source, |program| Vm::new().run(program))
+}
+
+/// Parses and checks the program, then runs it on `backend`.
+fn run_on(
+    source: &str,
+    backend: impl FnOnce(&Program) -> Result<Value, CompilerError>,
+) -> Result<Vec<CompilerError>, Vec<CompilerError>> {
+    let ast = parse(source)?;
+    let mut warnings = check(&ast)?;
+
+    // evaluation
+    match backend(&ast) {
+        Ok(_) => Ok(warnings),
+        Err(e) => {
+            warnings.push(e);
+            Err(warnings)
+        }
+    }
 }
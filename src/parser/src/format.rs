@@ -0,0 +1,499 @@
+use std::rc::Rc;
+
+use crate::{
+    parser::needs_semicolon,
+    types::{
+        Block, Field, Function, Literal, MatchArm, Node, NodeKind, Operator, Param, Pattern,
+        PatternKind, Program, UnaryOperator, VariantKind, VariantPattern,
+    },
+};
+
+const INDENT: &str = "    ";
+
+// binding power of atoms and postfix expressions, they never need parens
+const ATOM: u8 = u8::MAX;
+const PREFIX: u8 = 21;
+
+/// Prints a program back as source in the standard layout: four space
+/// indents, one statement per line and a blank line around declarations.
+/// Parsing the output gives back the same program. Comments are not part of
+/// the tree, so they are not printed.
+pub fn format_program(program: &Program) -> String {
+    let mut printer = Printer::default();
+    let mut previous: Option<&Node> = None;
+    for node in program.tree.iter() {
+        if previous.is_some_and(|p| is_item(p) || is_item(node)) {
+            printer.out.push('\n');
+        }
+        printer.statement(node);
+        previous = Some(node);
+    }
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+    // same restriction as the parser, `if (P { x: 1 }) == p {`
+    no_struct_literal: bool,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn statement(&mut self, node: &Node) {
+        match &node.kind {
+            NodeKind::Declaration(_) => {
+                let text = self.expr(node);
+                self.line(&format!("{text};"));
+            }
+            NodeKind::Function(function) => self.function(function),
+            NodeKind::Struct(s) => {
+                self.members(&format!("struct {}", s.name), &s.fields, &s.methods)
+            }
+            NodeKind::Shared(s) => {
+                self.members(&format!("shared {}", s.name), &s.fields, &s.methods)
+            }
+            NodeKind::Impl(i) => {
+                self.line(&format!("impl shared {} on {} {{", i.shared, i.target));
+                self.depth += 1;
+                for field in i.overrides.iter() {
+                    let value = self.expr(&field.value);
+                    self.line(&format!("{} = {value};", field.name));
+                }
+                for method in i.methods.iter() {
+                    self.function(method);
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+            NodeKind::Enum(e) => {
+                self.line(&format!("enum {} {{", e.name));
+                self.depth += 1;
+                for variant in e.variants.iter() {
+                    let text = match &variant.kind {
+                        VariantKind::Unit => variant.name.clone(),
+                        VariantKind::Tuple(types) => {
+                            let types: Vec<String> = types
+                                .iter()
+                                .map(|t| t.as_ref().map_or("_".to_string(), |t| t.to_string()))
+                                .collect();
+                            format!("{}({})", variant.name, types.join(", "))
+                        }
+                        VariantKind::Struct(fields) => {
+                            format!("{} {{ {} }}", variant.name, params(fields))
+                        }
+                    };
+                    self.line(&format!("{text},"));
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+            _ => {
+                let text = self.expr(node);
+                match needs_semicolon(node) {
+                    true => self.line(&format!("{text};")),
+                    false => self.line(&text),
+                }
+            }
+        }
+    }
+
+    /// Body of a struct or shared interface.
+    fn members(&mut self, header: &str, fields: &[Field], methods: &[Rc<Function>]) {
+        if fields.is_empty() && methods.is_empty() {
+            return self.line(&format!("{header} {{}}"));
+        }
+
+        self.line(&format!("{header} {{"));
+        self.depth += 1;
+        for field in fields.iter() {
+            let mut text = field.name.clone();
+            if field.optional {
+                text.push('?');
+            }
+            if let Some(annotation) = &field.annotation {
+                text.push_str(&format!(": {annotation}"));
+            }
+            if let Some(default) = &field.default {
+                text.push_str(&format!(" = {}", self.expr(default)));
+            }
+            self.line(&format!("{text};"));
+        }
+        for method in methods.iter() {
+            self.function(method);
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn function(&mut self, function: &Function) {
+        let mut header = format!("fn {}({})", function.name, params(&function.params));
+        if let Some(return_type) = &function.return_type {
+            header.push_str(&format!(" => {return_type}"));
+        }
+        match &function.body {
+            Some(body) => {
+                let body = self.block(body);
+                self.line(&format!("{header} {body}"));
+            }
+            None => self.line(&format!("{header};")),
+        }
+    }
+
+    /// `{ ... }` with the closing brace at the current indent and no trailing
+    /// newline, so it can continue an expression.
+    fn block(&mut self, block: &Block) -> String {
+        if block.body.is_empty() && block.value.is_none() {
+            return "{}".to_string();
+        }
+
+        let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+        let out = std::mem::take(&mut self.out);
+        self.depth += 1;
+        for node in block.body.iter() {
+            self.statement(node);
+        }
+        if let Some(value) = &block.value {
+            let text = self.expr(value);
+            self.line(&text);
+        }
+        self.depth -= 1;
+        let body = std::mem::replace(&mut self.out, out);
+        self.no_struct_literal = restricted;
+
+        format!("{{\n{body}{}}}", INDENT.repeat(self.depth))
+    }
+
+    fn expr(&mut self, node: &Node) -> String {
+        match &node.kind {
+            NodeKind::Literal(literal) => literal_text(literal),
+            NodeKind::Name(name) => name.clone(),
+            NodeKind::This => "this".to_string(),
+            NodeKind::Break => "break".to_string(),
+            NodeKind::Continue => "continue".to_string(),
+            NodeKind::Path { base, member } => format!("{base}::{member}"),
+            NodeKind::Declaration(declaration) => {
+                let annotation = match &declaration.annotation {
+                    Some(annotation) => format!(": {annotation}"),
+                    None => String::new(),
+                };
+                let value = self.expr(&declaration.value);
+                let keyword = match declaration.mutable {
+                    true => "let mut",
+                    false => "let",
+                };
+                match declaration.inferred {
+                    true => format!("{keyword} {} := {value}", declaration.symbol),
+                    false => format!("{keyword} {}{annotation} = {value}", declaration.symbol),
+                }
+            }
+            NodeKind::Assign { target, op, value } => {
+                let target = self.operand(target, ATOM);
+                let op = op.as_ref().map(Operator::to_string).unwrap_or_default();
+                format!("{target} {op}= {}", self.expr(value))
+            }
+            NodeKind::BinaryExpr { op, lhs, rhs } => {
+                let bp = binding_power(op);
+                let lhs = self.operand(lhs, bp);
+                let rhs = self.operand(rhs, bp + 1);
+                match op {
+                    Operator::Range => format!("{lhs}..{rhs}"),
+                    _ => format!("{lhs} {op} {rhs}"),
+                }
+            }
+            NodeKind::UnaryExpr { op, operand } => {
+                let op = match op {
+                    UnaryOperator::Negate => "-",
+                    UnaryOperator::Not => "!",
+                };
+                format!("{op}{}", self.operand(operand, PREFIX + 1))
+            }
+            NodeKind::Try(value) => format!("{}?", self.operand(value, ATOM)),
+            NodeKind::Call { callee, args } => {
+                let callee = self.operand(callee, ATOM);
+                format!("{callee}({})", self.list(args))
+            }
+            NodeKind::Get {
+                object,
+                field,
+                optional,
+            } => {
+                let dot = if *optional { "?." } else { "." };
+                format!("{}{dot}{field}", self.operand(object, ATOM))
+            }
+            NodeKind::Index { object, index } => {
+                let object = self.operand(object, ATOM);
+                format!("{object}[{}]", self.unrestricted(index))
+            }
+            NodeKind::Return(Some(value)) => format!("return {}", self.expr(value)),
+            NodeKind::Return(None) => "return".to_string(),
+            NodeKind::StructLiteral {
+                name,
+                variant,
+                fields,
+            } => {
+                let name = match variant {
+                    Some(variant) => format!("{name}::{variant}"),
+                    None => name.clone(),
+                };
+                let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| match &value.kind {
+                        NodeKind::Name(n) if n == field => field.clone(),
+                        _ => format!("{field}: {}", self.expr(value)),
+                    })
+                    .collect();
+                self.no_struct_literal = restricted;
+
+                let literal = match fields.is_empty() {
+                    true => format!("{name} {{}}"),
+                    false => format!("{name} {{ {} }}", fields.join(", ")),
+                };
+                match self.no_struct_literal {
+                    true => format!("({literal})"),
+                    false => literal,
+                }
+            }
+            NodeKind::Array(items) => format!("[{}]", self.list(items)),
+            NodeKind::Map(entries) if entries.is_empty() => "[:]".to_string(),
+            NodeKind::Map(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}: {}", self.unrestricted(key), self.unrestricted(value))
+                    })
+                    .collect();
+                format!("[{}]", entries.join(", "))
+            }
+            NodeKind::Tuple(items) if items.len() == 1 => format!("({},)", self.list(items)),
+            NodeKind::Tuple(items) => format!("({})", self.list(items)),
+            NodeKind::Block(block) => self.block(block),
+            NodeKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.condition(condition);
+                let mut text = format!("if {condition} {}", self.block(then));
+                if let Some(otherwise) = otherwise {
+                    text.push_str(&format!(" else {}", self.expr(otherwise)));
+                }
+                text
+            }
+            NodeKind::While { condition, body } => {
+                let condition = self.condition(condition);
+                format!("while {condition} {}", self.block(body))
+            }
+            NodeKind::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                let iterable = self.condition(iterable);
+                format!("for {binding} in {iterable} {}", self.block(body))
+            }
+            NodeKind::Match { scrutinee, arms } => self.match_expr(scrutinee, arms),
+            NodeKind::Closure { function, by_value } => {
+                let prefix = if *by_value { "move " } else { "" };
+                let params = match function.params.is_empty() {
+                    true => "||".to_string(),
+                    false => format!("|{}|", params(&function.params)),
+                };
+                let body = match function.body.as_ref() {
+                    Some(Block {
+                        body,
+                        value: Some(value),
+                    }) if body.is_empty() => self.expr(value),
+                    Some(block) => self.block(block),
+                    None => "{}".to_string(),
+                };
+                format!("{prefix}{params} {body}")
+            }
+            // declarations only appear as statements
+            NodeKind::Function(_)
+            | NodeKind::Struct(_)
+            | NodeKind::Shared(_)
+            | NodeKind::Impl(_)
+            | NodeKind::Enum(_) => {
+                let out = std::mem::take(&mut self.out);
+                self.statement(node);
+                let text = std::mem::replace(&mut self.out, out);
+                text.trim().to_string()
+            }
+        }
+    }
+
+    fn match_expr(&mut self, scrutinee: &Node, arms: &[MatchArm]) -> String {
+        let scrutinee = self.condition(scrutinee);
+        let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+        let out = std::mem::take(&mut self.out);
+        self.depth += 1;
+        for arm in arms.iter() {
+            let mut text = pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                text.push_str(&format!(" if {}", self.expr(guard)));
+            }
+            let body = self.expr(&arm.body);
+            match arm.body.kind {
+                NodeKind::Block(_) => self.line(&format!("{text} => {body}")),
+                _ => self.line(&format!("{text} => {body},")),
+            }
+        }
+        self.depth -= 1;
+        let body = std::mem::replace(&mut self.out, out);
+        self.no_struct_literal = restricted;
+
+        format!(
+            "match {scrutinee} {{\n{body}{}}}",
+            INDENT.repeat(self.depth)
+        )
+    }
+
+    /// An operand of an operator binding as tight as `min_bp`, in parens
+    /// when it binds looser.
+    fn operand(&mut self, node: &Node, min_bp: u8) -> String {
+        let text = self.expr(node);
+        match precedence(node) < min_bp {
+            true => format!("({text})"),
+            false => text,
+        }
+    }
+
+    /// Expression in front of a block, where struct literals need parens.
+    fn condition(&mut self, node: &Node) -> String {
+        let restricted = std::mem::replace(&mut self.no_struct_literal, true);
+        let text = self.expr(node);
+        self.no_struct_literal = restricted;
+        text
+    }
+
+    /// Expression between brackets or parens, where struct literals are fine.
+    fn unrestricted(&mut self, node: &Node) -> String {
+        let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+        let text = self.expr(node);
+        self.no_struct_literal = restricted;
+        text
+    }
+
+    fn list(&mut self, nodes: &[Node]) -> String {
+        let items: Vec<String> = nodes.iter().map(|n| self.unrestricted(n)).collect();
+        items.join(", ")
+    }
+}
+
+/// How tightly an expression holds together, expressions that run to the
+/// end of the surrounding one like closures and `if` bind loosest.
+fn precedence(node: &Node) -> u8 {
+    match &node.kind {
+        NodeKind::BinaryExpr { op, .. } => binding_power(op),
+        NodeKind::UnaryExpr { .. } => PREFIX,
+        // `1.5.floor()` would not scan
+        NodeKind::Literal(Literal::Float(_) | Literal::BigFloat(_)) => ATOM - 1,
+        NodeKind::Closure { .. }
+        | NodeKind::If { .. }
+        | NodeKind::Match { .. }
+        | NodeKind::Block(_)
+        | NodeKind::Return(_)
+        | NodeKind::Declaration(_)
+        | NodeKind::Assign { .. } => 0,
+        _ => ATOM,
+    }
+}
+
+/// Left binding power of an operator, matching the parser.
+fn binding_power(op: &Operator) -> u8 {
+    match op {
+        Operator::Range => 1,
+        Operator::Or => 3,
+        Operator::And => 5,
+        Operator::Equal | Operator::NotEqual => 7,
+        Operator::Greater | Operator::GreaterEqual | Operator::Less | Operator::LessEqual => 9,
+        Operator::Coalesce => 11,
+        Operator::BitOr => 15,
+        Operator::Add | Operator::Sub => 17,
+        Operator::Mult | Operator::Div => 19,
+    }
+}
+
+fn is_item(node: &Node) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::Function(_)
+            | NodeKind::Struct(_)
+            | NodeKind::Shared(_)
+            | NodeKind::Impl(_)
+            | NodeKind::Enum(_)
+    )
+}
+
+fn params(params: &[Param]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|p| match &p.annotation {
+            Some(annotation) => format!("{}: {annotation}", p.name),
+            None => p.name.clone(),
+        })
+        .collect();
+    params.join(", ")
+}
+
+fn literal_text(literal: &Literal) -> String {
+    match literal {
+        Literal::Float(n) => n.to_string(),
+        Literal::BigFloat(n) => n.to_string(),
+        Literal::String(s) => format!("\"{s}\""),
+        Literal::Char(c) => format!("'{c}'"),
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Nil => "nil".to_string(),
+    }
+}
+
+fn pattern(p: &Pattern) -> String {
+    match &p.kind {
+        PatternKind::Wildcard => "_".to_string(),
+        PatternKind::Literal(literal) => literal_text(literal),
+        PatternKind::Range(start, end) => {
+            format!("{}..{}", literal_text(start), literal_text(end))
+        }
+        PatternKind::Binding(name) => name.clone(),
+        PatternKind::Struct { name, fields } => format!("{name} {}", field_patterns(fields)),
+        PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } => match fields {
+            VariantPattern::Unit => format!("{enum_name}::{variant}"),
+            VariantPattern::Tuple(patterns) => {
+                let patterns: Vec<String> = patterns.iter().map(pattern).collect();
+                format!("{enum_name}::{variant}({})", patterns.join(", "))
+            }
+            VariantPattern::Struct(fields) => {
+                format!("{enum_name}::{variant} {}", field_patterns(fields))
+            }
+        },
+    }
+}
+
+/// `{ x, y: 0 }`, a field bound to its own name is written once.
+fn field_patterns(fields: &[(String, Pattern)]) -> String {
+    if fields.is_empty() {
+        return "{}".to_string();
+    }
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(field, p)| match &p.kind {
+            PatternKind::Binding(name) if name == field => field.clone(),
+            _ => format!("{field}: {}", pattern(p)),
+        })
+        .collect();
+    format!("{{ {} }}", fields.join(", "))
+}
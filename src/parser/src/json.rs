@@ -0,0 +1,473 @@
+use std::{
+    fmt::{self, Display, Formatter, Write},
+    rc::Rc,
+};
+
+use lexer::types::Span;
+
+use crate::types::{
+    Block, Field, Function, Literal, MatchArm, Node, NodeKind, Param, Pattern, PatternKind,
+    Program, Type, UnaryOperator, VariantKind, VariantPattern,
+};
+
+/// Minimal JSON document, enough to dump the syntax tree for other tools.
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            // JSON has no infinities or NaN
+            Json::Number(n) if n.is_finite() => write!(f, "{n}"),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Json::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "\"{key}\":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// The program as a JSON array of statements. Every node is an object with
+/// its `kind`, its `span` and the fields of that kind.
+pub fn program_to_json(program: &Program) -> Json {
+    Json::Array(program.tree.iter().map(node).collect())
+}
+
+fn node(n: &Node) -> Json {
+    let (kind, mut members) = match &n.kind {
+        NodeKind::Literal(l) => ("Literal", vec![("value", literal(l))]),
+        NodeKind::Name(name) => ("Name", vec![("name", string(name))]),
+        NodeKind::Declaration(d) => (
+            "Declaration",
+            vec![
+                ("symbol", string(&d.symbol)),
+                ("mutable", Json::Bool(d.mutable)),
+                ("type", annotation(&d.annotation)),
+                ("inferred", Json::Bool(d.inferred)),
+                ("value", node(&d.value)),
+            ],
+        ),
+        NodeKind::BinaryExpr { op, lhs, rhs } => (
+            "BinaryExpr",
+            vec![
+                ("op", string(&op.to_string())),
+                ("lhs", node(lhs)),
+                ("rhs", node(rhs)),
+            ],
+        ),
+        NodeKind::UnaryExpr { op, operand } => {
+            let op = match op {
+                UnaryOperator::Negate => "-",
+                UnaryOperator::Not => "!",
+            };
+            (
+                "UnaryExpr",
+                vec![("op", string(op)), ("operand", node(operand))],
+            )
+        }
+        NodeKind::Function(f) => ("Function", function(f)),
+        NodeKind::Call { callee, args } => (
+            "Call",
+            vec![("callee", node(callee)), ("args", nodes(args))],
+        ),
+        NodeKind::Return(value) => (
+            "Return",
+            vec![("value", value.as_deref().map_or(Json::Null, node))],
+        ),
+        NodeKind::Struct(s) => (
+            "Struct",
+            vec![
+                ("name", string(&s.name)),
+                ("fields", fields(&s.fields)),
+                ("methods", methods(&s.methods)),
+            ],
+        ),
+        NodeKind::StructLiteral {
+            name,
+            variant,
+            fields,
+        } => (
+            "StructLiteral",
+            vec![
+                ("name", string(name)),
+                ("variant", variant.as_deref().map_or(Json::Null, string)),
+                (
+                    "fields",
+                    Json::Array(
+                        fields
+                            .iter()
+                            .map(|(name, value)| {
+                                Json::Object(vec![("name", string(name)), ("value", node(value))])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        NodeKind::Path { base, member } => (
+            "Path",
+            vec![("base", string(base)), ("member", string(member))],
+        ),
+        NodeKind::Get {
+            object,
+            field,
+            optional,
+        } => (
+            "Get",
+            vec![
+                ("object", node(object)),
+                ("field", string(field)),
+                ("optional", Json::Bool(*optional)),
+            ],
+        ),
+        NodeKind::This => ("This", vec![]),
+        NodeKind::Shared(s) => (
+            "Shared",
+            vec![
+                ("name", string(&s.name)),
+                ("fields", fields(&s.fields)),
+                ("methods", methods(&s.methods)),
+            ],
+        ),
+        NodeKind::Impl(i) => (
+            "Impl",
+            vec![
+                ("shared", string(&i.shared)),
+                ("target", string(&i.target)),
+                (
+                    "overrides",
+                    Json::Array(
+                        i.overrides
+                            .iter()
+                            .map(|o| {
+                                Json::Object(vec![
+                                    ("name", string(&o.name)),
+                                    ("value", node(&o.value)),
+                                    ("span", span(o.span)),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+                ("methods", methods(&i.methods)),
+            ],
+        ),
+        NodeKind::Block(b) => ("Block", block(b)),
+        NodeKind::If {
+            condition,
+            then,
+            otherwise,
+        } => (
+            "If",
+            vec![
+                ("condition", node(condition)),
+                ("then", Json::Object(block(then))),
+                ("otherwise", otherwise.as_deref().map_or(Json::Null, node)),
+            ],
+        ),
+        NodeKind::While { condition, body } => (
+            "While",
+            vec![
+                ("condition", node(condition)),
+                ("body", Json::Object(block(body))),
+            ],
+        ),
+        NodeKind::For {
+            binding,
+            iterable,
+            body,
+        } => (
+            "For",
+            vec![
+                ("binding", string(binding)),
+                ("iterable", node(iterable)),
+                ("body", Json::Object(block(body))),
+            ],
+        ),
+        NodeKind::Break => ("Break", vec![]),
+        NodeKind::Continue => ("Continue", vec![]),
+        NodeKind::Match { scrutinee, arms } => (
+            "Match",
+            vec![
+                ("scrutinee", node(scrutinee)),
+                ("arms", Json::Array(arms.iter().map(arm).collect())),
+            ],
+        ),
+        NodeKind::Enum(e) => (
+            "Enum",
+            vec![
+                ("name", string(&e.name)),
+                (
+                    "variants",
+                    Json::Array(
+                        e.variants
+                            .iter()
+                            .map(|v| {
+                                let (kind, fields) = match &v.kind {
+                                    VariantKind::Unit => ("Unit", Json::Array(vec![])),
+                                    VariantKind::Tuple(types) => (
+                                        "Tuple",
+                                        Json::Array(types.iter().map(annotation).collect()),
+                                    ),
+                                    VariantKind::Struct(fields) => ("Struct", params(fields)),
+                                };
+                                Json::Object(vec![
+                                    ("name", string(&v.name)),
+                                    ("kind", string(kind)),
+                                    ("fields", fields),
+                                    ("span", span(v.span)),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+            ],
+        ),
+        NodeKind::Closure {
+            function: f,
+            by_value,
+        } => {
+            let mut members = function(f);
+            members.push(("by_value", Json::Bool(*by_value)));
+            ("Closure", members)
+        }
+        NodeKind::Array(items) => ("Array", vec![("items", nodes(items))]),
+        NodeKind::Map(entries) => (
+            "Map",
+            vec![(
+                "entries",
+                Json::Array(
+                    entries
+                        .iter()
+                        .map(|(key, value)| {
+                            Json::Object(vec![("key", node(key)), ("value", node(value))])
+                        })
+                        .collect(),
+                ),
+            )],
+        ),
+        NodeKind::Tuple(items) => ("Tuple", vec![("items", nodes(items))]),
+        NodeKind::Index { object, index } => (
+            "Index",
+            vec![("object", node(object)), ("index", node(index))],
+        ),
+        NodeKind::Try(value) => ("Try", vec![("value", node(value))]),
+        NodeKind::Assign { target, op, value } => (
+            "Assign",
+            vec![
+                ("target", node(target)),
+                (
+                    "op",
+                    op.as_ref().map_or(Json::Null, |op| string(&op.to_string())),
+                ),
+                ("value", node(value)),
+            ],
+        ),
+    };
+
+    members.insert(0, ("kind", string(kind)));
+    members.push(("span", span(n.span)));
+    Json::Object(members)
+}
+
+fn nodes(nodes: &[Node]) -> Json {
+    Json::Array(nodes.iter().map(node).collect())
+}
+
+fn block(block: &Block) -> Vec<(&'static str, Json)> {
+    vec![
+        ("body", nodes(&block.body)),
+        ("value", block.value.as_deref().map_or(Json::Null, node)),
+    ]
+}
+
+fn function(function: &Function) -> Vec<(&'static str, Json)> {
+    vec![
+        ("name", string(&function.name)),
+        ("params", params(&function.params)),
+        ("return_type", annotation(&function.return_type)),
+        (
+            "body",
+            function
+                .body
+                .as_ref()
+                .map_or(Json::Null, |b| Json::Object(block(b))),
+        ),
+    ]
+}
+
+fn methods(methods: &[Rc<Function>]) -> Json {
+    Json::Array(
+        methods
+            .iter()
+            .map(|m| {
+                let mut members = function(m);
+                members.push(("span", span(m.span)));
+                Json::Object(members)
+            })
+            .collect(),
+    )
+}
+
+fn fields(fields: &[Field]) -> Json {
+    Json::Array(
+        fields
+            .iter()
+            .map(|f| {
+                Json::Object(vec![
+                    ("name", string(&f.name)),
+                    ("optional", Json::Bool(f.optional)),
+                    ("type", annotation(&f.annotation)),
+                    ("default", f.default.as_deref().map_or(Json::Null, node)),
+                    ("span", span(f.span)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn params(params: &[Param]) -> Json {
+    Json::Array(
+        params
+            .iter()
+            .map(|p| {
+                Json::Object(vec![
+                    ("name", string(&p.name)),
+                    ("type", annotation(&p.annotation)),
+                    ("span", span(p.span)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn arm(arm: &MatchArm) -> Json {
+    Json::Object(vec![
+        ("pattern", pattern(&arm.pattern)),
+        ("guard", arm.guard.as_ref().map_or(Json::Null, node)),
+        ("body", node(&arm.body)),
+    ])
+}
+
+fn pattern(p: &Pattern) -> Json {
+    let field_patterns = |fields: &[(String, Pattern)]| {
+        Json::Array(
+            fields
+                .iter()
+                .map(|(name, p)| {
+                    Json::Object(vec![("name", string(name)), ("pattern", pattern(p))])
+                })
+                .collect(),
+        )
+    };
+
+    let (kind, mut members) = match &p.kind {
+        PatternKind::Wildcard => ("Wildcard", vec![]),
+        PatternKind::Literal(l) => ("Literal", vec![("value", literal(l))]),
+        PatternKind::Range(start, end) => (
+            "Range",
+            vec![("start", literal(start)), ("end", literal(end))],
+        ),
+        PatternKind::Binding(name) => ("Binding", vec![("name", string(name))]),
+        PatternKind::Struct { name, fields } => (
+            "Struct",
+            vec![("name", string(name)), ("fields", field_patterns(fields))],
+        ),
+        PatternKind::Variant {
+            enum_name,
+            variant,
+            fields,
+        } => {
+            let fields = match fields {
+                VariantPattern::Unit => Json::Null,
+                VariantPattern::Tuple(patterns) => {
+                    Json::Array(patterns.iter().map(pattern).collect())
+                }
+                VariantPattern::Struct(fields) => field_patterns(fields),
+            };
+            (
+                "Variant",
+                vec![
+                    ("enum", string(enum_name)),
+                    ("variant", string(variant)),
+                    ("fields", fields),
+                ],
+            )
+        }
+    };
+
+    members.insert(0, ("kind", string(kind)));
+    members.push(("span", span(p.span)));
+    Json::Object(members)
+}
+
+fn literal(literal: &Literal) -> Json {
+    match literal {
+        // through the shortest text form, so `0.1` does not come out as `0.10000000149011612`
+        Literal::Float(n) => Json::Number(n.to_string().parse().unwrap_or(*n as f64)),
+        Literal::BigFloat(n) => Json::Number(*n),
+        Literal::String(s) => string(s),
+        Literal::Char(c) => string(&c.to_string()),
+        Literal::Boolean(b) => Json::Bool(*b),
+        Literal::Nil => Json::Null,
+    }
+}
+
+fn annotation(annotation: &Option<Type>) -> Json {
+    annotation
+        .as_ref()
+        .map_or(Json::Null, |t| string(&t.to_string()))
+}
+
+fn span(span: Span) -> Json {
+    Json::Object(vec![
+        ("start", Json::Number(span.start as f64)),
+        ("end", Json::Number(span.end as f64)),
+        ("line", Json::Number(span.line as f64)),
+        ("col", Json::Number(span.col as f64)),
+    ])
+}
+
+fn string(s: &str) -> Json {
+    Json::String(s.to_string())
+}
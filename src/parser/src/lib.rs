@@ -1,2 +1,4 @@
+pub mod format;
+pub mod json;
 pub mod parser;
 pub mod types;
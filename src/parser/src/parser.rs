@@ -1,109 +1,431 @@
-use lexer::types::{IdentifierKind, OperatorKind, Token, TokenType};
-use std::{iter::Peekable, mem::discriminant, slice::Iter};
+mod control;
+mod expression;
+mod item;
+mod pattern;
+mod statement;
 
-use crate::types::{Declaration, Literal, Node, Program};
+pub(crate) use statement::needs_semicolon;
+
+use error::types::{CompilerError, ErrorTypes};
+use lexer::types::{IdentifierKind, OperatorKind, PunctuationKind, Span, Token, TokenType};
+use std::{
+    io::{self, Write},
+    iter::Peekable,
+    mem::discriminant,
+    slice::Iter,
+};
+
+use crate::types::{Declaration, Literal, Node, NodeKind, Program};
 
 pub struct Parser<'a> {
     iter: Peekable<Iter<'a, Token>>,
+    previous: Option<&'a Token>,
+    // braces opened and not closed yet by the tokens consumed so far
+    depth: usize,
+    diagnostics: Vec<CompilerError>,
+    // set while parsing the condition of `if`, `while` and `for`
+    no_struct_literal: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a mut Vec<Token>) -> Parser<'a> {
+    pub fn new(tokens: &'a [Token]) -> Parser<'a> {
         Parser {
             iter: tokens.iter().peekable().to_owned(),
+            previous: None,
+            depth: 0,
+            diagnostics: vec![],
+            no_struct_literal: false,
         }
     }
 
-    pub fn print_tokens(&mut self) {
-        while let Some(token) = self.iter.next() {
-            println!("{token}");
+    /// Writes the remaining tokens to stdout, one per line.
+    pub fn print_tokens(&mut self) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        for token in self.iter.by_ref() {
+            writeln!(out, "{token}")?;
         }
+        Ok(())
     }
 
-    pub fn parse_program(&mut self) -> Program {
+    /// Parses every statement in the token stream. A statement that fails to
+    /// parse is recorded and skipped, so all errors in a file are reported.
+    pub fn parse_program(&mut self) -> Result<Program, Vec<CompilerError>> {
         let mut program = Program::new();
-        // while let Some(t) = self.iter.next() {
-
-        // }
         while self.iter.len() > 0 {
-            if let Some(n) = self.next_node() {
-                program.push_node(n);
+            let remaining = self.iter.len();
+            let depth = self.depth;
+            match self.next_node() {
+                Ok(Some(n)) => program.push_node(n),
+                Ok(None) => {}
+                Err(e) => {
+                    self.diagnostics.push(e);
+                    // always make progress, even if the error consumed nothing
+                    if self.iter.len() == remaining {
+                        self.eat();
+                    }
+                    self.synchronize(depth);
+                }
             }
         }
-        program
+
+        if self.diagnostics.is_empty() {
+            Ok(program)
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
     }
 
-    pub fn next_node(&mut self) -> Option<Node> {
-        let t = match self.iter.next() {
-            Some(token) => token,
-            None => panic!("Error reading token"),
-        };
-        match &t.token_type {
-            TokenType::Identifiers(IdentifierKind::LET) => self.parser_declaration(),
-            TokenType::Number(n) => self.parse_num(n),
-            TokenType::String(n) => {
-                return Some(Node::Literal(Literal::String(n.clone())));
+    /// Skips tokens until the end of a broken statement that started inside
+    /// `depth` braces: just past its `;`, or past a `}` closing a block it
+    /// opened along with a `;` right after, or right before a `}` or a
+    /// keyword that starts a new statement. Blocks the statement opened are
+    /// skipped whole.
+    pub fn synchronize(&mut self, depth: usize) {
+        loop {
+            // the error consumed the `}` of the enclosing block
+            if self.depth < depth {
+                return;
             }
-            _ => None,
+
+            if self.depth == depth {
+                match self.previous.map(|t| &t.token_type) {
+                    Some(TokenType::Punctuation(PunctuationKind::SEMICOLON)) => return,
+                    Some(TokenType::Punctuation(PunctuationKind::RCURL)) => {
+                        self.eat_if(TokenType::Punctuation(PunctuationKind::SEMICOLON));
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+
+            match self.iter.peek().map(|t| &t.token_type) {
+                None | Some(TokenType::EOF) => return,
+                _ if self.depth > depth => {}
+                Some(TokenType::Punctuation(PunctuationKind::RCURL)) => return,
+                Some(TokenType::Identifiers(
+                    IdentifierKind::LET
+                    | IdentifierKind::FN
+                    | IdentifierKind::STRUCT
+                    | IdentifierKind::SHARED
+                    | IdentifierKind::IMPL
+                    | IdentifierKind::ENUM
+                    | IdentifierKind::IF
+                    | IdentifierKind::WHILE
+                    | IdentifierKind::FOR
+                    | IdentifierKind::MATCH
+                    | IdentifierKind::RETURN
+                    | IdentifierKind::BREAK
+                    | IdentifierKind::CONTINUE,
+                )) => return,
+                _ => {}
+            }
+
+            self.eat();
         }
     }
 
-    pub fn eat(&mut self) -> Option<&Token> {
-        self.iter.next()
+    pub fn next_node(&mut self) -> Result<Option<Node>, CompilerError> {
+        if let TokenType::EOF = self.peek()?.token_type {
+            self.eat();
+            return Ok(None);
+        }
+
+        // top level expression statements may leave out the trailing semicolon
+        let node = self.parse_statement()?;
+        self.eat_if(TokenType::Punctuation(PunctuationKind::SEMICOLON));
+        Ok(Some(node))
     }
 
-    pub fn expect_token(&mut self, expected: TokenType) -> Option<&Token> {
-        let token = match self.iter.next() {
-            Some(t) => t,
-            None => panic!("Error reading token."),
-        };
+    pub fn eat(&mut self) -> Option<&'a Token> {
+        let token = self.iter.next();
+        self.consumed(token)
+    }
+
+    /// Consumes the next token only when it has the expected type.
+    pub fn eat_if(&mut self, expected: TokenType) -> Option<&'a Token> {
+        let token = self.iter.next_if(|t| t.token_type == expected);
+        self.consumed(token)
+    }
 
-        if discriminant(&expected) == discriminant(&token.token_type) {
-            return Some(token);
+    fn consumed(&mut self, token: Option<&'a Token>) -> Option<&'a Token> {
+        match token.map(|t| &t.token_type) {
+            Some(TokenType::Punctuation(PunctuationKind::LCURL)) => self.depth += 1,
+            Some(TokenType::Punctuation(PunctuationKind::RCURL)) => {
+                self.depth = self.depth.saturating_sub(1)
+            }
+            _ => {}
         }
+        self.previous = token.or(self.previous);
+        token
+    }
 
-        panic!(
-            "[{},{}] ERROR: Unexpected token: {}",
-            token.line, token.col, token.literal
-        );
+    pub fn peek(&mut self) -> Result<&'a Token, CompilerError> {
+        match self.iter.peek() {
+            Some(t) => Ok(*t),
+            None => Err(end_of_input(self.previous)),
+        }
+    }
+
+    pub fn next_token(&mut self) -> Result<&'a Token, CompilerError> {
+        self.eat().ok_or_else(|| end_of_input(self.previous))
     }
 
-    pub fn check_semicolon(&mut self) {
-        self.expect_token(TokenType::Punctuation(
-            lexer::types::PunctuationKind::SEMICOLON,
-        ));
+    pub fn expect_token(&mut self, expected: TokenType) -> Result<&'a Token, CompilerError> {
+        let token = self.next_token()?;
+
+        // any name or literal will do, keywords and symbols must match exactly
+        let matches = match expected {
+            TokenType::Name(_)
+            | TokenType::String(_)
+            | TokenType::Number(_)
+            | TokenType::Char(_)
+            | TokenType::Bool(_) => discriminant(&expected) == discriminant(&token.token_type),
+            _ => expected == token.token_type,
+        };
+        if matches {
+            return Ok(token);
+        }
+
+        Err(parser_error(
+            token,
+            format!("Expected {}, found {}", describe(&expected), found(token)),
+        ))
     }
 
-    pub fn parser_declaration(&mut self) -> Option<Node> {
+    pub fn check_semicolon(&mut self) -> Result<(), CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::SEMICOLON))
+            .map_err(|e| e.with_help("statements end with a `;`"))?;
+        Ok(())
+    }
+
+    /// Span running from `start` to the end of the last consumed token.
+    pub fn span_from(&self, start: Span) -> Span {
+        match self.previous {
+            Some(t) => start.to(t.span),
+            None => start,
+        }
+    }
+
+    pub fn parser_declaration(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let mutable = self
+            .eat_if(TokenType::Identifiers(IdentifierKind::MUT))
+            .is_some();
         let mut symbol = String::new();
-        let next_token = self.expect_token(TokenType::Name(String::new()));
-        
-        if let TokenType::Name(n) = &next_token.unwrap().token_type {
+        let next_token = self.expect_token(TokenType::Name(String::new()))?;
+
+        if let TokenType::Name(n) = &next_token.token_type {
             symbol = n.clone();
         }
 
-        let _ = self.expect_token(TokenType::Operators(OperatorKind::EQUAL));
+        // `let x := 10;` keeps the type of its value, `let x: number = 10;`
+        // names it
+        let inferred = self
+            .eat_if(TokenType::Operators(OperatorKind::ColonEqual))
+            .is_some();
+        let mut annotation = None;
+        if !inferred {
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COLON))
+                .is_some()
+            {
+                annotation = Some(self.parse_type()?);
+            }
+            self.expect_token(TokenType::Operators(OperatorKind::EQUAL))?;
+        }
 
         // get value
-        let value = match self.next_node() {
-            Some(n) => n,
-            None => return None,
-        };
+        let value = self.parse_expression(0)?;
 
-        self.check_semicolon();
+        self.check_semicolon()?;
 
-        Some(Node::Declaration(Declaration {
-            symbol,
-            value: Box::new(value),
-        }))
+        Ok(Node::new(
+            NodeKind::Declaration(Declaration {
+                symbol,
+                mutable,
+                annotation,
+                inferred,
+                value: Box::new(value),
+            }),
+            self.span_from(start),
+        ))
     }
 
-    pub fn parse_num(&mut self, literal: &String) -> Option<Node> {
-        if let Ok(f) = literal.parse::<f32>() {
-            return Some(Node::Literal(Literal::Float(f)));
-        }
-        None
+    pub fn parse_num(&mut self, token: &Token, literal: &str) -> Result<Node, CompilerError> {
+        let literal = number_literal(token, literal)?;
+        Ok(Node::new(NodeKind::Literal(literal), token.span))
+    }
+}
+
+fn number_literal(token: &Token, literal: &str) -> Result<Literal, CompilerError> {
+    let big = match literal.parse::<f64>() {
+        Ok(f) => f,
+        Err(_) => return Err(parser_error(token, format!("Invalid number: {literal}"))),
+    };
+    // keep the f32 form only when it represents the literal exactly
+    let literal = match literal.parse::<f32>() {
+        Ok(f) if f as f64 == big => Literal::Float(f),
+        _ => Literal::BigFloat(big),
+    };
+    Ok(literal)
+}
+
+fn parser_error(token: &Token, message: String) -> CompilerError {
+    CompilerError::at(token.span, message, ErrorTypes::ParsingError)
+}
+
+fn unexpected(token: &Token) -> CompilerError {
+    parser_error(token, format!("Unexpected token: {}", found(token)))
+}
+
+/// Points at the last token consumed, the end of file once it has been
+/// consumed too.
+fn end_of_input(last: Option<&Token>) -> CompilerError {
+    CompilerError::at(
+        last.map_or_else(Span::default, |t| t.span),
+        "Unexpected end of input".to_string(),
+        ErrorTypes::ParsingError,
+    )
+}
+
+/// Human readable description of a kind of token, for error messages.
+fn describe(token_type: &TokenType) -> String {
+    match token_type {
+        TokenType::Name(_) => "a name".to_string(),
+        TokenType::Number(_) => "a number".to_string(),
+        TokenType::String(_) => "a string".to_string(),
+        TokenType::Char(_) => "a char".to_string(),
+        TokenType::Bool(_) => "a bool".to_string(),
+        TokenType::EOF => "end of file".to_string(),
+        t => format!("`{}`", t.to_literal()),
+    }
+}
+
+fn found(token: &Token) -> String {
+    match token.token_type {
+        TokenType::EOF => "end of file".to_string(),
+        _ => format!("`{}`", token.literal),
     }
 }
 
 pub fn build_ast() {}
+
+#[cfg(test)]
+mod tests {
+    use lexer::scanner::Scanner;
+
+    use super::Parser;
+    use crate::types::{Declaration, NodeKind};
+
+    /// The `let` declaration `source` consists of.
+    fn declaration(source: &str) -> Declaration {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+        let mut program = Parser::new(&scanner.tokens).parse_program().unwrap();
+        match program.tree.remove(0).kind {
+            NodeKind::Declaration(declaration) => declaration,
+            kind => panic!("expected a declaration, found {kind:?}"),
+        }
+    }
+
+    /// Messages of every error the parser reports for `source`.
+    fn errors(source: &str) -> Vec<String> {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+        match Parser::new(&scanner.tokens).parse_program() {
+            Ok(_) => vec![],
+            Err(errors) => errors.into_iter().map(|e| e.message.clone()).collect(),
+        }
+    }
+
+    #[test]
+    fn running_out_of_tokens_points_at_the_last_one() {
+        let mut scanner = Scanner::new("let a =");
+        scanner.scan_tokens().unwrap();
+        let tokens = &scanner.tokens[..scanner.tokens.len() - 1];
+        let Err(errors) = Parser::new(tokens).parse_program() else {
+            panic!("expected an error");
+        };
+        assert_eq!(errors[0].message, "Unexpected end of input");
+        assert_eq!(errors[0].span, Some(tokens[2].span));
+    }
+
+    #[test]
+    fn valid_source_has_no_errors() {
+        assert!(errors("let a = 1; a + 2;").is_empty());
+    }
+
+    #[test]
+    fn reports_an_error_in_every_statement() {
+        assert_eq!(
+            errors("let a = ; let b = 2; let c = );"),
+            ["Unexpected token: `;`", "Unexpected token: `)`"]
+        );
+    }
+
+    #[test]
+    fn recovers_at_the_next_statement() {
+        // the missing `;` is reported where the next `let` starts
+        assert_eq!(
+            errors("let a = (1 + ;\nlet b = 2\nlet c = 3;"),
+            ["Unexpected token: `;`", "Expected `;`, found `let`"]
+        );
+    }
+
+    #[test]
+    fn recovers_at_the_end_of_a_block() {
+        let errors = errors("fn f() { let x = ; } fn g() { let = 1; } let ok = 1;");
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn recovery_skips_blocks_the_broken_statement_opened() {
+        assert_eq!(
+            errors("let c = P { x: 1 + }; let d = 1;"),
+            ["Unexpected token: `}`"]
+        );
+        assert_eq!(
+            errors("let r = for i in 0..3 { i };"),
+            ["Unexpected token: `for`"]
+        );
+        assert_eq!(
+            errors("let r = for i in 0..3 { let y = i; }; let d = 1;"),
+            ["Unexpected token: `for`"]
+        );
+    }
+
+    #[test]
+    fn plain_declarations() {
+        let d = declaration("let mut x = 1;");
+        assert_eq!(d.symbol, "x");
+        assert!(d.mutable && !d.inferred && d.annotation.is_none());
+    }
+
+    #[test]
+    fn inferred_declarations() {
+        let d = declaration("let x := 5;");
+        assert!(d.inferred && !d.mutable && d.annotation.is_none());
+        assert!(declaration("let mut x := 5;").mutable);
+    }
+
+    #[test]
+    fn annotated_declarations() {
+        let d = declaration("let x: number = 10;");
+        assert_eq!(
+            d.annotation.map(|t| t.to_string()).as_deref(),
+            Some("number")
+        );
+        assert!(!d.inferred);
+        let d = declaration("let a: isAnimal = pick();");
+        assert_eq!(
+            d.annotation.map(|t| t.to_string()).as_deref(),
+            Some("isAnimal")
+        );
+    }
+
+    #[test]
+    fn declarations_need_a_value() {
+        assert_eq!(errors("let x: number;").len(), 1);
+        assert_eq!(errors("let x :=;").len(), 1);
+        assert_eq!(errors("let x: = 1;").len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+use error::types::CompilerError;
+use lexer::types::{IdentifierKind, Span, TokenType};
+
+use super::Parser;
+use crate::types::{Node, NodeKind};
+
+impl<'a> Parser<'a> {
+    /// `if cond { ... } else if cond { ... } else { ... }`, the `if` keyword
+    /// is already consumed.
+    pub fn parse_if(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let condition = self.parse_condition()?;
+        let then = self.parse_block()?;
+
+        let otherwise = match self.eat_if(TokenType::Identifiers(IdentifierKind::ELSE)) {
+            Some(_) => {
+                let token = self.peek()?;
+                let node = match token.token_type {
+                    TokenType::Identifiers(IdentifierKind::IF) => {
+                        self.eat();
+                        self.parse_if(token.span)?
+                    }
+                    _ => {
+                        let block = self.parse_block()?;
+                        Node::new(NodeKind::Block(block), self.span_from(token.span))
+                    }
+                };
+                Some(Box::new(node))
+            }
+            None => None,
+        };
+
+        Ok(Node::new(
+            NodeKind::If {
+                condition: Box::new(condition),
+                then,
+                otherwise,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// `while cond { ... }`, the `while` keyword is already consumed.
+    pub fn parse_while(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let condition = self.parse_condition()?;
+        let body = self.parse_block()?;
+
+        Ok(Node::new(
+            NodeKind::While {
+                condition: Box::new(condition),
+                body,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// `for x in 0..10 { ... }`, the `for` keyword is already consumed.
+    pub fn parse_for(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let binding = self.expect_name()?;
+        self.expect_token(TokenType::Identifiers(IdentifierKind::IN))?;
+        let iterable = self.parse_condition()?;
+        let body = self.parse_block()?;
+
+        Ok(Node::new(
+            NodeKind::For {
+                binding,
+                iterable: Box::new(iterable),
+                body,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// Expression in front of a block. Struct literals are not allowed here,
+    /// so `if animal {` opens the block instead of an `animal { ... }` literal.
+    fn parse_condition(&mut self) -> Result<Node, CompilerError> {
+        let restricted = std::mem::replace(&mut self.no_struct_literal, true);
+        let condition = self.parse_expression(0);
+        self.no_struct_literal = restricted;
+        condition
+    }
+}
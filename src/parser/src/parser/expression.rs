@@ -0,0 +1,736 @@
+use std::rc::Rc;
+
+use error::types::CompilerError;
+use lexer::types::{IdentifierKind, OperatorKind, PunctuationKind, Span, Token, TokenType};
+
+use super::{parser_error, unexpected, Parser};
+use crate::types::{Block, Function, Literal, Node, NodeKind, Operator, Param, UnaryOperator};
+
+// binding power of the prefix operators `-` and `!`, tighter than any infix operator
+const PREFIX_BP: u8 = 21;
+// `->` binds looser than arithmetic and tighter than comparisons, so
+// `a + b -> f == c` compares `f(a + b)` with `c`
+const PIPE_BP: u8 = 13;
+// assignment binds loosest and is right associative, `a = b = c`
+const ASSIGN_BP: u8 = 1;
+
+impl<'a> Parser<'a> {
+    /// Precedence climbing (Pratt) parser. Keeps folding infix operators into
+    /// the left hand side for as long as they bind tighter than `min_bp`.
+    pub fn parse_expression(&mut self, min_bp: u8) -> Result<Node, CompilerError> {
+        let lhs = self.parse_prefix()?;
+        self.parse_infix(lhs, min_bp)
+    }
+
+    /// Continues an expression whose left hand side is already parsed.
+    pub fn parse_infix(&mut self, mut lhs: Node, min_bp: u8) -> Result<Node, CompilerError> {
+        loop {
+            // postfix operators bind tighter than any infix operator
+            match self.iter.peek().map(|t| &t.token_type) {
+                Some(TokenType::Punctuation(PunctuationKind::LPAREN)) => {
+                    lhs = self.parse_call(lhs)?;
+                    continue;
+                }
+                Some(TokenType::Operators(OperatorKind::SingleArrow)) => {
+                    if PIPE_BP < min_bp {
+                        break;
+                    }
+                    self.eat();
+                    let target = self.parse_pipe_target()?;
+                    lhs = pipe(lhs, target);
+                    continue;
+                }
+                Some(TokenType::Punctuation(PunctuationKind::QUESTION)) => {
+                    self.eat();
+                    let span = self.span_from(lhs.span);
+                    lhs = Node::new(NodeKind::Try(Box::new(lhs)), span);
+                    continue;
+                }
+                Some(TokenType::Punctuation(PunctuationKind::LBRACE)) => {
+                    self.eat();
+                    let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+                    let index = self.parse_expression(0);
+                    self.no_struct_literal = restricted;
+                    let index = index?;
+                    self.expect_token(TokenType::Punctuation(PunctuationKind::RBRACE))?;
+                    let span = self.span_from(lhs.span);
+                    lhs = Node::new(
+                        NodeKind::Index {
+                            object: Box::new(lhs),
+                            index: Box::new(index),
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                Some(TokenType::Punctuation(
+                    kind @ (PunctuationKind::DOT | PunctuationKind::QuestionDot),
+                )) => {
+                    let optional = *kind == PunctuationKind::QuestionDot;
+                    self.eat();
+                    let field = self.expect_name()?;
+                    let span = self.span_from(lhs.span);
+                    lhs = Node::new(
+                        NodeKind::Get {
+                            object: Box::new(lhs),
+                            field,
+                            optional,
+                        },
+                        span,
+                    );
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Some(TokenType::Operators(kind)) = self.iter.peek().map(|t| &t.token_type) {
+                if let Some(op) = assign_operator(kind) {
+                    if ASSIGN_BP < min_bp {
+                        break;
+                    }
+                    lhs = self.parse_assign(lhs, op)?;
+                    continue;
+                }
+            }
+
+            let Some((l_bp, r_bp, op)) = self.peek_infix() else {
+                break;
+            };
+            if l_bp < min_bp {
+                break;
+            }
+
+            self.eat();
+            let rhs = self.parse_expression(r_bp)?;
+
+            let span = lhs.span.to(rhs.span);
+            lhs = Node::new(
+                NodeKind::BinaryExpr {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span,
+            );
+        }
+
+        Ok(lhs)
+    }
+
+    fn peek_infix(&mut self) -> Option<(u8, u8, Operator)> {
+        match self.iter.peek().map(|t| &t.token_type) {
+            Some(TokenType::Operators(kind)) => infix_binding_power(kind),
+            _ => None,
+        }
+    }
+
+    fn parse_prefix(&mut self) -> Result<Node, CompilerError> {
+        let token = self.next_token()?;
+        let literal = |l: Literal| Ok(Node::new(NodeKind::Literal(l), token.span));
+
+        match &token.token_type {
+            TokenType::Number(n) => self.parse_num(token, n),
+            TokenType::String(s) => literal(Literal::String(s.clone())),
+            TokenType::Char(c) => literal(Literal::Char(*c)),
+            TokenType::Bool(b) => literal(Literal::Boolean(*b)),
+            TokenType::Identifiers(IdentifierKind::NIL) => literal(Literal::Nil),
+            TokenType::Name(n) => self.parse_name(n, token.span),
+            TokenType::Identifiers(IdentifierKind::THIS) => {
+                Ok(Node::new(NodeKind::This, token.span))
+            }
+            TokenType::Punctuation(PunctuationKind::LPAREN) => {
+                // `if (Point { x: 1 }) == p {` is allowed with the parens
+                let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+                let expr = self.parse_group(token.span);
+                self.no_struct_literal = restricted;
+                expr
+            }
+            TokenType::Punctuation(PunctuationKind::LBRACE) => {
+                let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+                let expr = self.parse_collection(token.span);
+                self.no_struct_literal = restricted;
+                expr
+            }
+            TokenType::Operators(OperatorKind::MINUS) => {
+                self.parse_unary(UnaryOperator::Negate, token.span)
+            }
+            TokenType::Operators(OperatorKind::BANG) => {
+                self.parse_unary(UnaryOperator::Not, token.span)
+            }
+            TokenType::Identifiers(IdentifierKind::RETURN) => self.parse_return(token.span),
+            TokenType::Identifiers(IdentifierKind::IF) => self.parse_if(token.span),
+            TokenType::Identifiers(IdentifierKind::MATCH) => self.parse_match(token.span),
+            TokenType::Operators(OperatorKind::BAR | OperatorKind::OR) => {
+                self.parse_closure(token, false)
+            }
+            TokenType::Identifiers(IdentifierKind::FN) => self.parse_fn_literal(token.span, false),
+            TokenType::Identifiers(IdentifierKind::MOVE) => {
+                let next = self.next_token()?;
+                match next.token_type {
+                    TokenType::Operators(OperatorKind::BAR | OperatorKind::OR) => {
+                        self.parse_closure(next, true)
+                    }
+                    TokenType::Identifiers(IdentifierKind::FN) => {
+                        self.parse_fn_literal(token.span, true)
+                    }
+                    _ => Err(unexpected(next).with_help("`move` goes in front of a closure")),
+                }
+            }
+            TokenType::Punctuation(PunctuationKind::LCURL) => {
+                let block = self.parse_block_body()?;
+                Ok(Node::new(
+                    NodeKind::Block(block),
+                    self.span_from(token.span),
+                ))
+            }
+            TokenType::Identifiers(IdentifierKind::BREAK) => {
+                Ok(Node::new(NodeKind::Break, token.span))
+            }
+            TokenType::Identifiers(IdentifierKind::CONTINUE) => {
+                Ok(Node::new(NodeKind::Continue, token.span))
+            }
+            _ => Err(unexpected(token)),
+        }
+    }
+
+    /// `(expr)`, or a tuple when there is a comma, `(a, b)`, `(a,)` and `()`.
+    /// The opening paren is already consumed.
+    fn parse_group(&mut self, start: Span) -> Result<Node, CompilerError> {
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_some()
+        {
+            return Ok(Node::new(NodeKind::Tuple(vec![]), self.span_from(start)));
+        }
+
+        let mut expr = self.parse_expression(0)?;
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+            .is_none()
+        {
+            self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+            // the parens belong to the expression's span
+            expr.span = self.span_from(start);
+            return Ok(expr);
+        }
+
+        let mut items = vec![expr];
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_none()
+        {
+            items.push(self.parse_expression(0)?);
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+                break;
+            }
+        }
+        Ok(Node::new(NodeKind::Tuple(items), self.span_from(start)))
+    }
+
+    /// `[a, b]` or `[key: value]`, the first entry decides which. `[:]` is an
+    /// empty map. The opening bracket is already consumed.
+    fn parse_collection(&mut self, start: Span) -> Result<Node, CompilerError> {
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::COLON))
+            .is_some()
+        {
+            self.expect_token(TokenType::Punctuation(PunctuationKind::RBRACE))?;
+            return Ok(Node::new(NodeKind::Map(vec![]), self.span_from(start)));
+        }
+
+        let mut items = vec![];
+        let mut entries = vec![];
+        let mut is_map = false;
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RBRACE))
+            .is_none()
+        {
+            let item = self.parse_expression(0)?;
+            if items.is_empty() && entries.is_empty() {
+                is_map = matches!(
+                    self.peek()?.token_type,
+                    TokenType::Punctuation(PunctuationKind::COLON)
+                );
+            }
+            match is_map {
+                true => {
+                    self.expect_token(TokenType::Punctuation(PunctuationKind::COLON))?;
+                    entries.push((item, self.parse_expression(0)?));
+                }
+                false => items.push(item),
+            }
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RBRACE))?;
+                break;
+            }
+        }
+
+        let kind = match is_map {
+            true => NodeKind::Map(entries),
+            false => NodeKind::Array(items),
+        };
+        Ok(Node::new(kind, self.span_from(start)))
+    }
+
+    /// A name on its own, a `Base::member` path or a struct literal.
+    fn parse_name(&mut self, name: &str, start: Span) -> Result<Node, CompilerError> {
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::DoubleColon))
+            .is_some()
+        {
+            let member = self.expect_name()?;
+            if let TokenType::Punctuation(PunctuationKind::LCURL) = self.peek()?.token_type {
+                if !self.no_struct_literal {
+                    return self.parse_struct_literal(name, Some(member), start);
+                }
+            }
+            return Ok(Node::new(
+                NodeKind::Path {
+                    base: name.to_string(),
+                    member,
+                },
+                self.span_from(start),
+            ));
+        }
+
+        if let TokenType::Punctuation(PunctuationKind::LCURL) = self.peek()?.token_type {
+            if !self.no_struct_literal {
+                return self.parse_struct_literal(name, None, start);
+            }
+        }
+
+        Ok(Node::new(NodeKind::Name(name.to_string()), start))
+    }
+
+    /// `Name { field: value, shorthand, }` or `Enum::Variant { field: value }`
+    fn parse_struct_literal(
+        &mut self,
+        name: &str,
+        variant: Option<String>,
+        start: Span,
+    ) -> Result<Node, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        let mut fields = vec![];
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.expect_token(TokenType::Name(String::new()))?;
+            let value = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+                Some(_) => self.parse_expression(0)?,
+                // `Sheep { name }` is short for `Sheep { name: name }`
+                None => Node::new(NodeKind::Name(token.literal.clone()), token.span),
+            };
+            fields.push((token.literal.clone(), value));
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+                break;
+            }
+        }
+
+        Ok(Node::new(
+            NodeKind::StructLiteral {
+                name: name.to_string(),
+                variant,
+                fields,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    /// What a value is piped into: a callee such as `f`, `m::f` or `obj.f`,
+    /// optionally called with the arguments that follow the piped value.
+    /// Postfix operators after it apply to the result of the pipe, so
+    /// `x -> f()?` is `f(x)?` and `x -> f().len()` is `f(x).len()`.
+    fn parse_pipe_target(&mut self) -> Result<Node, CompilerError> {
+        let mut target = self.parse_prefix()?;
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::DOT))
+            .is_some()
+        {
+            let field = self.expect_name()?;
+            let span = self.span_from(target.span);
+            target = Node::new(
+                NodeKind::Get {
+                    object: Box::new(target),
+                    field,
+                    optional: false,
+                },
+                span,
+            );
+        }
+        if let Some(TokenType::Punctuation(PunctuationKind::LPAREN)) =
+            self.iter.peek().map(|t| &t.token_type)
+        {
+            target = self.parse_call(target)?;
+        }
+        Ok(target)
+    }
+
+    /// `callee(arg, ...)` with an optional trailing comma.
+    fn parse_call(&mut self, callee: Node) -> Result<Node, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LPAREN))?;
+        let mut args = vec![];
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_none()
+        {
+            args.push(self.parse_expression(0)?);
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+                break;
+            }
+        }
+
+        let span = self.span_from(callee.span);
+        Ok(Node::new(
+            NodeKind::Call {
+                callee: Box::new(callee),
+                args,
+            },
+            span,
+        ))
+    }
+
+    /// `|a, b| body` where `opening` is the first `|`, or `||` for no
+    /// parameters. The body is a single expression, which may be a block.
+    fn parse_closure(&mut self, opening: &Token, by_value: bool) -> Result<Node, CompilerError> {
+        let mut params = vec![];
+        if let TokenType::Operators(OperatorKind::BAR) = opening.token_type {
+            while self
+                .eat_if(TokenType::Operators(OperatorKind::BAR))
+                .is_none()
+            {
+                let token = self.expect_token(TokenType::Name(String::new()))?;
+                let annotation = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+                    Some(_) => Some(self.parse_type()?),
+                    None => None,
+                };
+                params.push(Param {
+                    name: token.literal.clone(),
+                    annotation,
+                    span: self.span_from(token.span),
+                });
+
+                if self
+                    .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                    .is_none()
+                {
+                    self.expect_token(TokenType::Operators(OperatorKind::BAR))?;
+                    break;
+                }
+            }
+        }
+
+        let body = self.parse_expression(0)?;
+        self.closure(opening.span, params, body, by_value)
+    }
+
+    /// `fn(params) { ... }` or `fn(params) => expr`, the `fn` keyword is
+    /// already consumed.
+    pub fn parse_fn_literal(&mut self, start: Span, by_value: bool) -> Result<Node, CompilerError> {
+        let params = self.parse_params()?;
+        let body = match self.eat_if(TokenType::Operators(OperatorKind::ARROW)) {
+            Some(_) => self.parse_expression(0)?,
+            None => {
+                let token = self.peek()?;
+                let block = self.parse_block()?;
+                Node::new(NodeKind::Block(block), self.span_from(token.span))
+            }
+        };
+        self.closure(start, params, body, by_value)
+    }
+
+    fn closure(
+        &mut self,
+        start: Span,
+        params: Vec<Param>,
+        body: Node,
+        by_value: bool,
+    ) -> Result<Node, CompilerError> {
+        // the body is the value of the closure's block
+        let body = match body.kind {
+            NodeKind::Block(block) => block,
+            _ => Block {
+                body: vec![],
+                value: Some(Box::new(body)),
+            },
+        };
+
+        let span = self.span_from(start);
+        let function = Function {
+            name: String::new(),
+            params,
+            return_type: None,
+            body: Some(body),
+            span,
+        };
+        Ok(Node::new(
+            NodeKind::Closure {
+                function: Rc::new(function),
+                by_value,
+            },
+            span,
+        ))
+    }
+
+    /// `return` with an optional value. A value is absent when the next token
+    /// closes the surrounding statement or expression.
+    fn parse_return(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let value = match self.peek()?.token_type {
+            TokenType::Punctuation(
+                PunctuationKind::SEMICOLON | PunctuationKind::RCURL | PunctuationKind::COMMA,
+            )
+            | TokenType::EOF => None,
+            _ => Some(Box::new(self.parse_expression(0)?)),
+        };
+        Ok(Node::new(NodeKind::Return(value), self.span_from(start)))
+    }
+
+    /// `target = value` or `target += value`, the target is already parsed.
+    fn parse_assign(&mut self, target: Node, op: Option<Operator>) -> Result<Node, CompilerError> {
+        let token = self.next_token()?;
+        let assignable = match &target.kind {
+            NodeKind::Name(_) => true,
+            // `a?.b = c` would have nothing to assign to when `a` is nil
+            NodeKind::Get { optional, .. } => !optional,
+            // slices are copies, assigning to one would be lost
+            NodeKind::Index { index, .. } => !matches!(
+                index.kind,
+                NodeKind::BinaryExpr {
+                    op: Operator::Range,
+                    ..
+                }
+            ),
+            _ => false,
+        };
+        if !assignable {
+            return Err(parser_error(
+                token,
+                "Invalid assignment target, expected a variable, field or index".to_string(),
+            ));
+        }
+
+        let value = self.parse_expression(ASSIGN_BP)?;
+        let span = target.span.to(value.span);
+        Ok(Node::new(
+            NodeKind::Assign {
+                target: Box::new(target),
+                op,
+                value: Box::new(value),
+            },
+            span,
+        ))
+    }
+
+    fn parse_unary(&mut self, op: UnaryOperator, start: Span) -> Result<Node, CompilerError> {
+        let operand = self.parse_expression(PREFIX_BP)?;
+        let span = start.to(operand.span);
+        Ok(Node::new(
+            NodeKind::UnaryExpr {
+                op,
+                operand: Box::new(operand),
+            },
+            span,
+        ))
+    }
+}
+
+/// Left and right binding power of an infix operator. All binary operators
+/// are left associative, so the right side always binds one step tighter.
+fn infix_binding_power(kind: &OperatorKind) -> Option<(u8, u8, Operator)> {
+    let bp = match kind {
+        OperatorKind::SPREAD => (1, 2, Operator::Range),
+        OperatorKind::OR => (3, 4, Operator::Or),
+        OperatorKind::AND => (5, 6, Operator::And),
+        OperatorKind::EqualEqual => (7, 8, Operator::Equal),
+        OperatorKind::BangEqual => (7, 8, Operator::NotEqual),
+        OperatorKind::GREATER => (9, 10, Operator::Greater),
+        OperatorKind::GreaterEqual => (9, 10, Operator::GreaterEqual),
+        OperatorKind::LESS => (9, 10, Operator::Less),
+        OperatorKind::LessEqual => (9, 10, Operator::LessEqual),
+        OperatorKind::QuestionQuestion => (11, 12, Operator::Coalesce),
+        OperatorKind::BAR => (15, 16, Operator::BitOr),
+        OperatorKind::PLUS => (17, 18, Operator::Add),
+        OperatorKind::MINUS => (17, 18, Operator::Sub),
+        OperatorKind::STAR => (19, 20, Operator::Mult),
+        OperatorKind::SLASH => (19, 20, Operator::Div),
+        _ => return None,
+    };
+    Some(bp)
+}
+
+/// The operator an assignment applies before storing, `None` for plain `=`.
+fn assign_operator(kind: &OperatorKind) -> Option<Option<Operator>> {
+    let op = match kind {
+        OperatorKind::EQUAL => None,
+        OperatorKind::PlusEqual => Some(Operator::Add),
+        OperatorKind::MinusEqual => Some(Operator::Sub),
+        OperatorKind::StarEqual => Some(Operator::Mult),
+        OperatorKind::SlashEqual => Some(Operator::Div),
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// Desugars `value -> f(args)` into `f(value, args)` and `value -> f` into
+/// `f(value)`.
+fn pipe(value: Node, target: Node) -> Node {
+    let span = value.span.to(target.span);
+    let (callee, mut args) = match target.kind {
+        NodeKind::Call { callee, args } => (callee, args),
+        _ => (Box::new(target), vec![]),
+    };
+    args.insert(0, value);
+    Node::new(NodeKind::Call { callee, args }, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use lexer::scanner::Scanner;
+
+    use crate::{
+        format::format_program,
+        parser::Parser,
+        types::{Literal, Node, NodeKind, Program, UnaryOperator},
+    };
+
+    fn program(source: &str) -> Program {
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+        Parser::new(&scanner.tokens).parse_program().unwrap()
+    }
+
+    /// Parses `source` and prints it back in the standard layout.
+    fn parse(source: &str) -> String {
+        format_program(&program(source)).trim_end().to_string()
+    }
+
+    /// Parses a single expression and prints its shape, `(+ 1 (* 2 3))`.
+    fn shape(source: &str) -> String {
+        tree(&program(source).tree[0])
+    }
+
+    fn tree(node: &Node) -> String {
+        match &node.kind {
+            NodeKind::BinaryExpr { op, lhs, rhs } => {
+                format!("({op} {} {})", tree(lhs), tree(rhs))
+            }
+            NodeKind::UnaryExpr { op, operand } => {
+                let op = match op {
+                    UnaryOperator::Negate => "-",
+                    UnaryOperator::Not => "!",
+                };
+                format!("({op} {})", tree(operand))
+            }
+            NodeKind::Literal(Literal::Float(n)) => n.to_string(),
+            NodeKind::Literal(Literal::Boolean(b)) => b.to_string(),
+            NodeKind::Name(name) => name.clone(),
+            NodeKind::Call { callee, args } => {
+                let args: Vec<String> = args.iter().map(tree).collect();
+                format!("{}({})", tree(callee), args.join(", "))
+            }
+            NodeKind::Get {
+                object,
+                field,
+                optional,
+            } => {
+                let dot = if *optional { "?." } else { "." };
+                format!("{}{dot}{field}", tree(object))
+            }
+            NodeKind::Path { base, member } => format!("{base}::{member}"),
+            kind => panic!("unexpected node {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(shape("1 + 2 * 3"), "(+ 1 (* 2 3))");
+        assert_eq!(shape("1 * 2 + 3"), "(+ (* 1 2) 3)");
+        assert_eq!(shape("a / b - c * d"), "(- (/ a b) (* c d))");
+    }
+
+    #[test]
+    fn binary_operators_are_left_associative() {
+        assert_eq!(shape("1 - 2 - 3"), "(- (- 1 2) 3)");
+        assert_eq!(shape("a / b / c"), "(/ (/ a b) c)");
+        assert_eq!(shape("a == b == c"), "(== (== a b) c)");
+    }
+
+    #[test]
+    fn comparison_and_logic_precedence() {
+        assert_eq!(shape("a + 1 < b * 2"), "(< (+ a 1) (* b 2))");
+        assert_eq!(shape("a < b == c > d"), "(== (< a b) (> c d))");
+        assert_eq!(shape("a || b && c"), "(|| a (&& b c))");
+        assert_eq!(shape("a == b && c != d"), "(&& (== a b) (!= c d))");
+        assert_eq!(shape("a | b + c"), "(| a (+ b c))");
+    }
+
+    #[test]
+    fn prefix_operators_bind_tightest() {
+        assert_eq!(shape("-a * b"), "(* (- a) b)");
+        assert_eq!(shape("!a == b"), "(== (! a) b)");
+        assert_eq!(shape("- -a"), "(- (- a))");
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        assert_eq!(shape("(1 + 2) * 3"), "(* (+ 1 2) 3)");
+        assert_eq!(shape("a - (b - c)"), "(- a (- b c))");
+    }
+
+    #[test]
+    fn pipe_into_name_or_call() {
+        assert_eq!(shape("x -> f"), "f(x)");
+        assert_eq!(shape("x -> f(1, 2)"), "f(x, 1, 2)");
+        assert_eq!(shape("x -> m.f(1)"), "m.f(x, 1)");
+        assert_eq!(shape("x -> Math::max(1)"), "Math::max(x, 1)");
+    }
+
+    #[test]
+    fn pipes_chain_left_to_right() {
+        assert_eq!(shape("x -> f -> g(1)"), "g(f(x), 1)");
+    }
+
+    #[test]
+    fn pipe_binds_looser_than_arithmetic() {
+        assert_eq!(shape("a + b -> f == c"), "(== f((+ a b)) c)");
+        assert_eq!(shape("a * 2 -> f(1)"), "f((* a 2), 1)");
+        assert_eq!(shape("x -> f + 1"), "(+ f(x) 1)");
+    }
+
+    #[test]
+    fn postfix_after_pipe_applies_to_result() {
+        assert_eq!(parse("x -> f()?;"), "f(x)?;");
+        assert_eq!(parse("x -> f().len();"), "f(x).len();");
+        assert_eq!(parse("x -> f()[0];"), "f(x)[0];");
+        assert_eq!(parse("x -> f()?.y;"), "f(x)?.y;");
+        assert_eq!(parse("x -> f(1)(2);"), "f(x, 1)(2);");
+    }
+
+    #[test]
+    fn optional_chains_and_coalescing() {
+        assert_eq!(shape("a?.b.c"), "a?.b.c");
+        assert_eq!(shape("a?.b ?? c"), "(?? a?.b c)");
+        assert_eq!(shape("1 + 2 ?? 5"), "(?? (+ 1 2) 5)");
+        assert_eq!(shape("a ?? 1 < 2"), "(< (?? a 1) 2)");
+    }
+}
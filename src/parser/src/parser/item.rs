@@ -0,0 +1,354 @@
+use std::rc::Rc;
+
+use error::types::CompilerError;
+use lexer::types::{IdentifierKind, OperatorKind, PunctuationKind, Span, TokenType, TypeKind};
+
+use super::{parser_error, unexpected, Parser};
+use crate::types::{
+    EnumDecl, Field, FieldOverride, Function, ImplDecl, Node, NodeKind, Param, SharedDecl,
+    StructDecl, Type, Variant, VariantKind,
+};
+
+impl<'a> Parser<'a> {
+    /// `fn name(param: type, ...) => type { ... }`, the `fn` keyword is
+    /// already consumed.
+    pub fn parse_function(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let function = self.parse_function_decl(start, false)?;
+        Ok(Node::new(
+            NodeKind::Function(Rc::new(function)),
+            self.span_from(start),
+        ))
+    }
+
+    /// Parses a function after its `fn` keyword. With `signature_only` set the
+    /// body may be replaced by a `;`, as in required interface methods.
+    fn parse_function_decl(
+        &mut self,
+        start: Span,
+        signature_only: bool,
+    ) -> Result<Function, CompilerError> {
+        let name = self.expect_name()?;
+        let params = self.parse_params()?;
+        let return_type = self.parse_return_type()?;
+
+        let body = match self.peek()?.token_type {
+            TokenType::Punctuation(PunctuationKind::SEMICOLON) if signature_only => {
+                self.eat();
+                None
+            }
+            TokenType::Punctuation(PunctuationKind::SEMICOLON) => {
+                return Err(
+                    parser_error(self.peek()?, format!("Function '{name}' needs a body"))
+                        .with_help("only methods of a `shared` interface may leave out their body"),
+                );
+            }
+            _ => Some(self.parse_block()?),
+        };
+
+        Ok(Function {
+            name,
+            params,
+            return_type,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Methods and fields between braces, shared by structs and interfaces.
+    fn parse_members(
+        &mut self,
+        signature_only: bool,
+    ) -> Result<(Vec<Field>, Vec<Rc<Function>>), CompilerError> {
+        let mut fields = vec![];
+        let mut methods = vec![];
+
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.peek()?;
+            match token.token_type {
+                TokenType::Identifiers(IdentifierKind::FN) => {
+                    self.eat();
+                    methods.push(Rc::new(
+                        self.parse_function_decl(token.span, signature_only)?,
+                    ));
+                }
+                _ => fields.push(self.parse_field()?),
+            }
+        }
+
+        Ok((fields, methods))
+    }
+
+    /// `struct Name { field: type; fn method() { ... } }`, the `struct`
+    /// keyword is already consumed.
+    pub fn parse_struct(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let name = self.expect_name()?;
+        let (fields, methods) = self.parse_members(false)?;
+
+        Ok(Node::new(
+            NodeKind::Struct(Rc::new(StructDecl {
+                name,
+                fields,
+                methods,
+            })),
+            self.span_from(start),
+        ))
+    }
+
+    /// `enum Name { Unit, Tuple(type, ...), Struct { field: type, ... } }`, the
+    /// `enum` keyword is already consumed.
+    pub fn parse_enum(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let name = self.expect_name()?;
+        let mut variants = vec![];
+
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.expect_token(TokenType::Name(String::new()))?;
+            let kind = match self.peek()?.token_type {
+                TokenType::Punctuation(PunctuationKind::LPAREN) => {
+                    VariantKind::Tuple(self.parse_variant_types()?)
+                }
+                TokenType::Punctuation(PunctuationKind::LCURL) => {
+                    VariantKind::Struct(self.parse_variant_fields()?)
+                }
+                _ => VariantKind::Unit,
+            };
+            variants.push(Variant {
+                name: token.literal.clone(),
+                kind,
+                span: self.span_from(token.span),
+            });
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+                break;
+            }
+        }
+
+        Ok(Node::new(
+            NodeKind::Enum(Rc::new(EnumDecl { name, variants })),
+            self.span_from(start),
+        ))
+    }
+
+    /// `(type, _, ...)` of a tuple variant, `_` leaves a position untyped.
+    fn parse_variant_types(&mut self) -> Result<Vec<Option<Type>>, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LPAREN))?;
+        let mut types = vec![];
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_none()
+        {
+            match self.peek()?.token_type {
+                TokenType::Name(ref n) if n == "_" => {
+                    self.eat();
+                    types.push(None);
+                }
+                _ => types.push(Some(self.parse_type()?)),
+            }
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+                break;
+            }
+        }
+
+        Ok(types)
+    }
+
+    /// `{ name: type, ... }` of a struct variant.
+    fn parse_variant_fields(&mut self) -> Result<Vec<Param>, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        let mut fields = vec![];
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.expect_token(TokenType::Name(String::new()))?;
+            let annotation = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+                Some(_) => Some(self.parse_type()?),
+                None => None,
+            };
+            fields.push(Param {
+                name: token.literal.clone(),
+                annotation,
+                span: self.span_from(token.span),
+            });
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+                break;
+            }
+        }
+
+        Ok(fields)
+    }
+
+    /// `shared Name { field?: type = default; fn method() => type; }`, the
+    /// `shared` keyword is already consumed.
+    pub fn parse_shared(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let name = self.expect_name()?;
+        let (fields, methods) = self.parse_members(true)?;
+
+        Ok(Node::new(
+            NodeKind::Shared(Rc::new(SharedDecl {
+                name,
+                fields,
+                methods,
+            })),
+            self.span_from(start),
+        ))
+    }
+
+    /// `impl shared Interface on Target { field = value; fn method() { ... } }`,
+    /// the `impl` keyword is already consumed.
+    pub fn parse_impl(&mut self, start: Span) -> Result<Node, CompilerError> {
+        self.expect_token(TokenType::Identifiers(IdentifierKind::SHARED))?;
+        let shared = self.expect_name()?;
+        self.expect_token(TokenType::Identifiers(IdentifierKind::ON))?;
+        let target = self.expect_name()?;
+
+        let mut overrides = vec![];
+        let mut methods = vec![];
+
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.peek()?;
+            match token.token_type {
+                TokenType::Identifiers(IdentifierKind::FN) => {
+                    self.eat();
+                    methods.push(Rc::new(self.parse_function_decl(token.span, false)?));
+                }
+                _ => {
+                    let name = self.expect_name()?;
+                    self.expect_token(TokenType::Operators(OperatorKind::EQUAL))?;
+                    let value = Rc::new(self.parse_expression(0)?);
+                    self.check_semicolon()?;
+                    overrides.push(FieldOverride {
+                        name,
+                        value,
+                        span: self.span_from(token.span),
+                    });
+                }
+            }
+        }
+
+        let span = self.span_from(start);
+        Ok(Node::new(
+            NodeKind::Impl(Rc::new(ImplDecl {
+                shared,
+                target,
+                overrides,
+                methods,
+                span,
+            })),
+            span,
+        ))
+    }
+
+    /// `name?: type = default;` where the `?`, the type and the default are
+    /// all optional.
+    pub fn parse_field(&mut self) -> Result<Field, CompilerError> {
+        let token = self.expect_token(TokenType::Name(String::new()))?;
+        let optional = self
+            .eat_if(TokenType::Punctuation(PunctuationKind::QUESTION))
+            .is_some();
+        let annotation = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+            Some(_) => Some(self.parse_type()?),
+            None => None,
+        };
+        let default = match self.eat_if(TokenType::Operators(OperatorKind::EQUAL)) {
+            Some(_) => Some(Rc::new(self.parse_expression(0)?)),
+            None => None,
+        };
+        self.check_semicolon()?;
+
+        Ok(Field {
+            name: token.literal.clone(),
+            optional,
+            annotation,
+            default,
+            span: self.span_from(token.span),
+        })
+    }
+
+    /// `(a, b: string, c)` with an optional trailing comma.
+    pub fn parse_params(&mut self) -> Result<Vec<Param>, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LPAREN))?;
+        let mut params = vec![];
+
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_none()
+        {
+            let token = self.expect_token(TokenType::Name(String::new()))?;
+            let annotation = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+                Some(_) => Some(self.parse_type()?),
+                None => None,
+            };
+            params.push(Param {
+                name: token.literal.clone(),
+                annotation,
+                span: self.span_from(token.span),
+            });
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+                break;
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Optional `=> type` after a parameter list.
+    pub fn parse_return_type(&mut self) -> Result<Option<Type>, CompilerError> {
+        match self.eat_if(TokenType::Operators(OperatorKind::ARROW)) {
+            Some(_) => Ok(Some(self.parse_type()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn parse_type(&mut self) -> Result<Type, CompilerError> {
+        let token = self.next_token()?;
+        let t = match &token.token_type {
+            TokenType::Type(TypeKind::STRING) => Type::String,
+            TokenType::Type(TypeKind::NUMBER) => Type::Number,
+            TokenType::Type(TypeKind::CHAR) => Type::Char,
+            TokenType::Type(TypeKind::BOOL) => Type::Bool,
+            TokenType::Identifiers(IdentifierKind::NIL) => Type::Nil,
+            TokenType::Identifiers(IdentifierKind::THIS) => Type::This,
+            TokenType::Name(name) => Type::Named(name.clone()),
+            _ => return Err(unexpected(token)),
+        };
+        Ok(t)
+    }
+
+    pub fn expect_name(&mut self) -> Result<String, CompilerError> {
+        let token = self.expect_token(TokenType::Name(String::new()))?;
+        Ok(token.literal.clone())
+    }
+}
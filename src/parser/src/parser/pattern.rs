@@ -0,0 +1,196 @@
+use error::types::CompilerError;
+use lexer::types::{IdentifierKind, OperatorKind, PunctuationKind, Span, TokenType};
+
+use super::{number_literal, parser_error, unexpected, Parser};
+use crate::types::{Literal, MatchArm, Node, NodeKind, Pattern, PatternKind, VariantPattern};
+
+impl<'a> Parser<'a> {
+    /// `match value { pattern if guard => expr, ... }`, the `match` keyword is
+    /// already consumed. The comma after an arm is optional when its body is a block.
+    pub fn parse_match(&mut self, start: Span) -> Result<Node, CompilerError> {
+        let restricted = std::mem::replace(&mut self.no_struct_literal, true);
+        let scrutinee = self.parse_expression(0);
+        self.no_struct_literal = restricted;
+        let scrutinee = scrutinee?;
+
+        let mut arms = vec![];
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let pattern = self.parse_pattern()?;
+            let guard = match self.eat_if(TokenType::Identifiers(IdentifierKind::IF)) {
+                Some(_) => Some(self.parse_expression(0)?),
+                None => None,
+            };
+            self.expect_token(TokenType::Operators(OperatorKind::ARROW))?;
+            let body = self.parse_expression(0)?;
+            let is_block = matches!(body.kind, NodeKind::Block(_));
+
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+                && !is_block
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+                break;
+            }
+        }
+
+        Ok(Node::new(
+            NodeKind::Match {
+                scrutinee: Box::new(scrutinee),
+                arms,
+            },
+            self.span_from(start),
+        ))
+    }
+
+    pub fn parse_pattern(&mut self) -> Result<Pattern, CompilerError> {
+        let token = self.peek()?;
+
+        let kind = match &token.token_type {
+            TokenType::Name(name) if name == "_" => {
+                self.eat();
+                PatternKind::Wildcard
+            }
+            TokenType::Name(name) => {
+                self.eat();
+                if self
+                    .eat_if(TokenType::Punctuation(PunctuationKind::DoubleColon))
+                    .is_some()
+                {
+                    let variant = self.expect_name()?;
+                    PatternKind::Variant {
+                        enum_name: name.clone(),
+                        variant,
+                        fields: self.parse_variant_pattern()?,
+                    }
+                } else if let TokenType::Punctuation(PunctuationKind::LCURL) =
+                    self.peek()?.token_type
+                {
+                    self.eat();
+                    PatternKind::Struct {
+                        name: name.clone(),
+                        fields: self.parse_field_patterns()?,
+                    }
+                } else if matches!(name.as_str(), "Ok" | "Err")
+                    && self.peek()?.token_type == TokenType::Punctuation(PunctuationKind::LPAREN)
+                {
+                    // `Ok(v)` is short for `Result::Ok(v)`
+                    PatternKind::Variant {
+                        enum_name: "Result".to_string(),
+                        variant: name.clone(),
+                        fields: self.parse_variant_pattern()?,
+                    }
+                } else {
+                    PatternKind::Binding(name.clone())
+                }
+            }
+            _ => {
+                let literal = self.parse_literal_pattern()?;
+                match self.eat_if(TokenType::Operators(OperatorKind::SPREAD)) {
+                    Some(_) => PatternKind::Range(literal, self.parse_literal_pattern()?),
+                    None => PatternKind::Literal(literal),
+                }
+            }
+        };
+
+        Ok(Pattern {
+            kind,
+            span: self.span_from(token.span),
+        })
+    }
+
+    /// What follows `Enum::Variant` in a pattern: nothing, `(a, b)` or `{ a, b: 0 }`.
+    fn parse_variant_pattern(&mut self) -> Result<VariantPattern, CompilerError> {
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::LCURL))
+            .is_some()
+        {
+            return Ok(VariantPattern::Struct(self.parse_field_patterns()?));
+        }
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::LPAREN))
+            .is_none()
+        {
+            return Ok(VariantPattern::Unit);
+        }
+
+        let mut patterns = vec![];
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RPAREN))
+            .is_none()
+        {
+            patterns.push(self.parse_pattern()?);
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RPAREN))?;
+                break;
+            }
+        }
+        Ok(VariantPattern::Tuple(patterns))
+    }
+
+    /// `{ x, y: pattern }` after the opening brace, a bare field name binds
+    /// the field to a variable of the same name.
+    fn parse_field_patterns(&mut self) -> Result<Vec<(String, Pattern)>, CompilerError> {
+        let mut fields = vec![];
+        while self
+            .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+            .is_none()
+        {
+            let token = self.expect_token(TokenType::Name(String::new()))?;
+            let pattern = match self.eat_if(TokenType::Punctuation(PunctuationKind::COLON)) {
+                Some(_) => self.parse_pattern()?,
+                None => Pattern {
+                    kind: PatternKind::Binding(token.literal.clone()),
+                    span: token.span,
+                },
+            };
+            fields.push((token.literal.clone(), pattern));
+
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::COMMA))
+                .is_none()
+            {
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+                break;
+            }
+        }
+        Ok(fields)
+    }
+
+    /// A literal in a pattern, numbers may be negative.
+    fn parse_literal_pattern(&mut self) -> Result<Literal, CompilerError> {
+        let token = self.next_token()?;
+        let literal = match &token.token_type {
+            TokenType::Number(n) => number_literal(token, n)?,
+            TokenType::Operators(OperatorKind::MINUS) => {
+                let number = self.next_token()?;
+                let TokenType::Number(n) = &number.token_type else {
+                    return Err(parser_error(
+                        number,
+                        format!("Expected a number after `-`, found `{}`", number.literal),
+                    ));
+                };
+                number_literal(number, &format!("-{n}"))?
+            }
+            TokenType::String(s) => Literal::String(s.clone()),
+            TokenType::Char(c) => Literal::Char(*c),
+            TokenType::Bool(b) => Literal::Boolean(*b),
+            TokenType::Identifiers(IdentifierKind::NIL) => Literal::Nil,
+            _ => return Err(unexpected(token).with_help("expected a pattern")),
+        };
+        Ok(literal)
+    }
+}
@@ -0,0 +1,154 @@
+use error::types::CompilerError;
+use lexer::types::{IdentifierKind, PunctuationKind, TokenType};
+
+use super::Parser;
+use crate::types::{Block, Node, NodeKind};
+
+impl<'a> Parser<'a> {
+    /// Parses a single statement. Expression statements are returned without
+    /// consuming their terminating `;`, callers decide whether one is needed.
+    pub fn parse_statement(&mut self) -> Result<Node, CompilerError> {
+        let t = self.peek()?;
+        match &t.token_type {
+            TokenType::Identifiers(IdentifierKind::LET) => {
+                self.eat();
+                self.parser_declaration(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::FN) => {
+                self.eat();
+                // `fn(x) { ... }` without a name is a closure expression
+                match self.peek()?.token_type {
+                    TokenType::Punctuation(PunctuationKind::LPAREN) => {
+                        let closure = self.parse_fn_literal(t.span, false)?;
+                        self.parse_infix(closure, 0)
+                    }
+                    _ => self.parse_function(t.span),
+                }
+            }
+            TokenType::Identifiers(IdentifierKind::STRUCT) => {
+                self.eat();
+                self.parse_struct(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::SHARED) => {
+                self.eat();
+                self.parse_shared(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::IMPL) => {
+                self.eat();
+                self.parse_impl(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::ENUM) => {
+                self.eat();
+                self.parse_enum(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::WHILE) => {
+                self.eat();
+                self.parse_while(t.span)
+            }
+            TokenType::Identifiers(IdentifierKind::FOR) => {
+                self.eat();
+                self.parse_for(t.span)
+            }
+            _ => self.parse_expression(0),
+        }
+    }
+
+    /// Parses `{ ... }`. Statements that fail to parse are recorded and
+    /// skipped so the rest of the block is still checked.
+    pub fn parse_block(&mut self) -> Result<Block, CompilerError> {
+        self.expect_token(TokenType::Punctuation(PunctuationKind::LCURL))?;
+        self.parse_block_body()
+    }
+
+    /// The rest of a block after its opening `{`.
+    pub fn parse_block_body(&mut self) -> Result<Block, CompilerError> {
+        // struct literals are fine again inside the braces of a condition's block
+        let restricted = std::mem::replace(&mut self.no_struct_literal, false);
+        let block = self.parse_block_items();
+        self.no_struct_literal = restricted;
+        block
+    }
+
+    fn parse_block_items(&mut self) -> Result<Block, CompilerError> {
+        let mut block = Block::default();
+        let depth = self.depth;
+
+        loop {
+            if self
+                .eat_if(TokenType::Punctuation(PunctuationKind::RCURL))
+                .is_some()
+            {
+                return Ok(block);
+            }
+            if let TokenType::EOF = self.peek()?.token_type {
+                // let expect_token report the missing brace
+                self.expect_token(TokenType::Punctuation(PunctuationKind::RCURL))?;
+            }
+
+            match self.parse_block_item(&mut block) {
+                Ok(()) => {}
+                Err(e) => {
+                    self.diagnostics.push(e);
+                    self.synchronize(depth);
+                    // the broken statement ran into the block's `}`
+                    if self.depth < depth {
+                        return Ok(block);
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_block_item(&mut self, block: &mut Block) -> Result<(), CompilerError> {
+        let node = self.parse_statement()?;
+
+        // a trailing `if`, `match` or block gives the enclosing block its value
+        if let (
+            NodeKind::If { .. } | NodeKind::Match { .. } | NodeKind::Block(_),
+            TokenType::Punctuation(PunctuationKind::RCURL),
+        ) = (&node.kind, &self.peek()?.token_type)
+        {
+            block.value = Some(Box::new(node));
+            return Ok(());
+        }
+
+        if !needs_semicolon(&node) {
+            block.body.push(node);
+            return Ok(());
+        }
+
+        if self
+            .eat_if(TokenType::Punctuation(PunctuationKind::SEMICOLON))
+            .is_some()
+        {
+            block.body.push(node);
+            return Ok(());
+        }
+
+        // an expression right before the closing brace is the block's value
+        if let TokenType::Punctuation(PunctuationKind::RCURL) = self.peek()?.token_type {
+            block.value = Some(Box::new(node));
+            return Ok(());
+        }
+
+        self.check_semicolon()
+    }
+}
+
+/// Declarations consume their own `;` and block-like statements don't need one.
+pub(crate) fn needs_semicolon(node: &Node) -> bool {
+    !matches!(
+        node.kind,
+        NodeKind::Declaration(_)
+            | NodeKind::Function(_)
+            | NodeKind::Struct(_)
+            | NodeKind::Shared(_)
+            | NodeKind::Impl(_)
+            | NodeKind::Block(_)
+            | NodeKind::If { .. }
+            | NodeKind::While { .. }
+            | NodeKind::For { .. }
+            | NodeKind::Match { .. }
+            | NodeKind::Enum(_)
+    )
+}
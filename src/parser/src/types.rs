@@ -1,9 +1,19 @@
-use std::fmt::{self, Display, Formatter};
+use lexer::types::Span;
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    rc::Rc,
+};
 
 pub struct Program {
     pub tree: Vec<Node>,
 }
 
+impl Default for Program {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Program {
     pub fn new() -> Program {
         Program { tree: vec![] }
@@ -21,21 +31,219 @@ impl Display for Program {
         let mut s: String = String::new();
         for node in self.tree.iter() {
             s.push_str(node.to_string().as_str());
-            s.push_str("\n");
+            s.push('\n');
         }
         write!(f, "{}", s)
     }
 }
 
+/// A node of the AST along with the span of source it was parsed from.
+#[derive(Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+impl Node {
+    pub fn new(kind: NodeKind, span: Span) -> Node {
+        Node { kind, span }
+    }
+
+    /// Direct child nodes, including the bodies of functions and methods,
+    /// for passes that walk the whole tree.
+    pub fn children(&self) -> Vec<&Node> {
+        let mut children = vec![];
+        match &self.kind {
+            NodeKind::Literal(_)
+            | NodeKind::Name(_)
+            | NodeKind::Path { .. }
+            | NodeKind::This
+            | NodeKind::Break
+            | NodeKind::Continue
+            | NodeKind::Enum(_) => {}
+            NodeKind::Declaration(d) => children.push(&*d.value),
+            NodeKind::BinaryExpr { lhs, rhs, .. } => children.extend([&**lhs, &**rhs]),
+            NodeKind::UnaryExpr { operand, .. } | NodeKind::Try(operand) => children.push(operand),
+            NodeKind::Function(f) | NodeKind::Closure { function: f, .. } => {
+                function_children(f, &mut children)
+            }
+            NodeKind::Call { callee, args } => {
+                children.push(callee);
+                children.extend(args.iter());
+            }
+            NodeKind::Return(value) => children.extend(value.as_deref()),
+            NodeKind::Struct(s) => {
+                children.extend(s.fields.iter().filter_map(|f| f.default.as_deref()));
+                for method in s.methods.iter() {
+                    function_children(method, &mut children);
+                }
+            }
+            NodeKind::StructLiteral { fields, .. } => {
+                children.extend(fields.iter().map(|(_, value)| value))
+            }
+            NodeKind::Get { object, .. } => children.push(object),
+            NodeKind::Array(items) | NodeKind::Tuple(items) => children.extend(items.iter()),
+            NodeKind::Map(entries) => {
+                for (key, value) in entries.iter() {
+                    children.extend([key, value]);
+                }
+            }
+            NodeKind::Index { object, index } => children.extend([&**object, &**index]),
+            NodeKind::Assign { target, value, .. } => children.extend([&**target, &**value]),
+            NodeKind::Shared(s) => {
+                children.extend(s.fields.iter().filter_map(|f| f.default.as_deref()));
+                for method in s.methods.iter() {
+                    function_children(method, &mut children);
+                }
+            }
+            NodeKind::Impl(i) => {
+                children.extend(i.overrides.iter().map(|o| &*o.value));
+                for method in i.methods.iter() {
+                    function_children(method, &mut children);
+                }
+            }
+            NodeKind::Block(block) => block_children(block, &mut children),
+            NodeKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                children.push(condition);
+                block_children(then, &mut children);
+                children.extend(otherwise.as_deref());
+            }
+            NodeKind::While { condition, body } => {
+                children.push(condition);
+                block_children(body, &mut children);
+            }
+            NodeKind::For { iterable, body, .. } => {
+                children.push(iterable);
+                block_children(body, &mut children);
+            }
+            NodeKind::Match { scrutinee, arms } => {
+                children.push(scrutinee);
+                for arm in arms.iter() {
+                    children.extend(arm.guard.as_ref());
+                    children.push(&arm.body);
+                }
+            }
+        }
+        children
+    }
+}
+
+fn block_children<'a>(block: &'a Block, children: &mut Vec<&'a Node>) {
+    children.extend(block.body.iter());
+    children.extend(block.value.as_deref());
+}
+
+fn function_children<'a>(function: &'a Function, children: &mut Vec<&'a Node>) {
+    if let Some(body) = &function.body {
+        block_children(body, children);
+    }
+}
+
+// spans are left out so AST dumps stay readable
+impl Debug for Node {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone)]
-pub enum Node {
+pub enum NodeKind {
     Literal(Literal),
+    Name(String),
     Declaration(Declaration),
     BinaryExpr {
         op: Operator,
         lhs: Box<Node>,
         rhs: Box<Node>,
     },
+    UnaryExpr {
+        op: UnaryOperator,
+        operand: Box<Node>,
+    },
+    Function(Rc<Function>),
+    Call {
+        callee: Box<Node>,
+        args: Vec<Node>,
+    },
+    Return(Option<Box<Node>>),
+    Struct(Rc<StructDecl>),
+    /// `Sheep { name: "Dolly" }`, shorthand fields hold a `Name` node. Struct
+    /// like enum variants, `Shape::Rect { w: 1, h: 2 }`, also set `variant`.
+    StructLiteral {
+        name: String,
+        variant: Option<String>,
+        fields: Vec<(String, Node)>,
+    },
+    /// `Sheep::new`
+    Path {
+        base: String,
+        member: String,
+    },
+    /// `animal.name`, or `animal?.name` which is `nil` when the object is.
+    Get {
+        object: Box<Node>,
+        field: String,
+        optional: bool,
+    },
+    This,
+    Shared(Rc<SharedDecl>),
+    Impl(Rc<ImplDecl>),
+    /// `{ ... }` with its own scope.
+    Block(Block),
+    /// `else if` chains nest another `If` as the `otherwise` branch.
+    If {
+        condition: Box<Node>,
+        then: Block,
+        otherwise: Option<Box<Node>>,
+    },
+    While {
+        condition: Box<Node>,
+        body: Block,
+    },
+    /// `for x in 0..10 { ... }`
+    For {
+        binding: String,
+        iterable: Box<Node>,
+        body: Block,
+    },
+    Break,
+    Continue,
+    /// `match value { pattern if guard => expr, ... }`
+    Match {
+        scrutinee: Box<Node>,
+        arms: Vec<MatchArm>,
+    },
+    Enum(Rc<EnumDecl>),
+    /// `|x| x * 2` or `fn(x) { ... }`. A `move` closure captures the values
+    /// of the variables it sees instead of referring to them.
+    Closure {
+        function: Rc<Function>,
+        by_value: bool,
+    },
+    /// `[1, 2, 3]`
+    Array(Vec<Node>),
+    /// `["key": value, ...]`, `[:]` when empty.
+    Map(Vec<(Node, Node)>),
+    /// `(1, "a")`, `(1,)` or `()`
+    Tuple(Vec<Node>),
+    /// `a[i]`, or a slice when the index is a range, `a[0..n]`.
+    Index {
+        object: Box<Node>,
+        index: Box<Node>,
+    },
+    /// `value?`, the `Ok` value of a `Result` or an early return of an `Err`.
+    Try(Box<Node>),
+    /// `target = value` or `target += value`, where the target is a name,
+    /// a field or an index. The value of the assignment is the stored value.
+    Assign {
+        target: Box<Node>,
+        op: Option<Operator>,
+        value: Box<Node>,
+    },
 }
 
 impl Display for Node {
@@ -50,6 +258,47 @@ pub enum Operator {
     Sub,
     Div,
     Mult,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    And,
+    Or,
+    BitOr,
+    Range,
+    /// `a ?? b`, `b` when `a` is nil. Only evaluates `b` when needed.
+    Coalesce,
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let s = match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Div => "/",
+            Operator::Mult => "*",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::BitOr => "|",
+            Operator::Range => "..",
+            Operator::Coalesce => "??",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
 }
 
 #[derive(Debug, Clone)]
@@ -59,10 +308,198 @@ pub enum Literal {
     String(String),
     Char(char),
     Boolean(bool),
+    Nil,
 }
 
 #[derive(Debug, Clone)]
 pub struct Declaration {
     pub symbol: String,
+    /// `let mut x = ...`, plain bindings cannot be assigned again.
+    pub mutable: bool,
+    pub annotation: Option<Type>,
+    /// `let x := value`, the binding keeps the type of its initial value.
+    pub inferred: bool,
     pub value: Box<Node>,
 }
+
+/// Statements between braces. A trailing expression without a `;` is the
+/// value of the block.
+#[derive(Debug, Clone, Default)]
+pub struct Block {
+    pub body: Vec<Node>,
+    pub value: Option<Box<Node>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    /// Empty for closures.
+    pub name: String,
+    pub params: Vec<Param>,
+    pub return_type: Option<Type>,
+    /// Only required methods of a `shared` interface have no body.
+    pub body: Option<Block>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructDecl {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Rc<Function>>,
+}
+
+/// Data member declaration, `name?: string = "default";`. Optional fields
+/// may be left out of a struct literal and are `nil` unless they have a default.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub optional: bool,
+    pub annotation: Option<Type>,
+    pub default: Option<Rc<Node>>,
+    pub span: Span,
+}
+
+/// `shared Name { ... }` interface controlling both data and behaviour.
+/// Methods without a body must be provided by every implementation.
+#[derive(Debug, Clone)]
+pub struct SharedDecl {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Rc<Function>>,
+}
+
+/// `impl shared Interface on Target { ... }`
+#[derive(Debug, Clone)]
+pub struct ImplDecl {
+    pub shared: String,
+    pub target: String,
+    pub overrides: Vec<FieldOverride>,
+    pub methods: Vec<Rc<Function>>,
+    pub span: Span,
+}
+
+/// `name = value;` inside an impl, replacing the interface's default.
+#[derive(Debug, Clone)]
+pub struct FieldOverride {
+    pub name: String,
+    pub value: Rc<Node>,
+    pub span: Span,
+}
+
+/// `enum Shape { Empty, Circle(number), Rect { w: number, h: number } }`
+#[derive(Debug, Clone)]
+pub struct EnumDecl {
+    pub name: String,
+    pub variants: Vec<Variant>,
+}
+
+impl EnumDecl {
+    /// The built-in `enum Result { Ok(_), Err(_) }` every program can use.
+    pub fn result() -> EnumDecl {
+        let variant = |name: &str| Variant {
+            name: name.to_string(),
+            kind: VariantKind::Tuple(vec![None]),
+            span: Span::default(),
+        };
+        EnumDecl {
+            name: "Result".to_string(),
+            variants: vec![variant("Ok"), variant("Err")],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: String,
+    pub kind: VariantKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum VariantKind {
+    Unit,
+    Tuple(Vec<Option<Type>>),
+    Struct(Vec<Param>),
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Node>,
+    pub body: Node,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternKind {
+    /// `_`
+    Wildcard,
+    Literal(Literal),
+    /// `0..10`, half open like `for` ranges.
+    Range(Literal, Literal),
+    /// A plain name matches anything and binds it.
+    Binding(String),
+    /// `Point { x, y: 0 }`, fields that are left out match anything.
+    Struct {
+        name: String,
+        fields: Vec<(String, Pattern)>,
+    },
+    /// `Shape::Circle(r)`, `Shape::Rect { w, h }` or `Shape::Empty`
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: VariantPattern,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum VariantPattern {
+    Unit,
+    Tuple(Vec<Pattern>),
+    Struct(Vec<(String, Pattern)>),
+}
+
+impl Pattern {
+    /// Whether the pattern matches every value, like `_` or a binding.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self.kind, PatternKind::Wildcard | PatternKind::Binding(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub annotation: Option<Type>,
+    pub span: Span,
+}
+
+/// Type written in an annotation, e.g. `name: string` or `=> this`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    String,
+    Number,
+    Char,
+    Bool,
+    Nil,
+    This,
+    Named(String),
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Type::String => write!(f, "string"),
+            Type::Number => write!(f, "number"),
+            Type::Char => write!(f, "char"),
+            Type::Bool => write!(f, "bool"),
+            Type::Nil => write!(f, "nil"),
+            Type::This => write!(f, "this"),
+            Type::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::{self, BufRead, Write};
+
+use error::{render::Renderer, types::CompilerError};
+use interpreter::value::Value;
+use ion::{parse, Engine};
+use lexer::{
+    scanner::Scanner,
+    types::{PunctuationKind, TokenType},
+};
+use parser::parser::Parser;
+
+const HELP: &str = "\
+:tokens <code>  print the tokens of <code>
+:ast <code>     print the syntax tree of <code>
+:quit           leave the REPL (or Ctrl-D)";
+
+/// Reads, evaluates and prints lines until `:quit` or the end of input.
+/// Declarations made on one line stay visible on the next.
+pub fn start(color: bool) {
+    let mut engine = Engine::new();
+    engine.set_stack_size(crate::STACK_SIZE);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    println!("Ion REPL, :help for commands");
+    while let Some(entry) = read_entry(&mut lines) {
+        let entry = entry.trim();
+
+        match entry.split_once(char::is_whitespace).unwrap_or((entry, "")) {
+            ("", _) => {}
+            (":quit" | ":q", _) => break,
+            (":help", _) => println!("{HELP}"),
+            (":tokens", code) => {
+                let mut scanner = Scanner::new(code);
+                match scanner.scan_tokens() {
+                    Ok(()) => {
+                        let _ = Parser::new(&scanner.tokens).print_tokens();
+                    }
+                    Err(errors) => report(code, &errors, color),
+                }
+            }
+            (":ast", code) => match parse(&terminate(code)) {
+                Ok(program) => program.print_nodes(),
+                Err(errors) => report(code, &errors, color),
+            },
+            (command, _) if command.starts_with(':') => {
+                eprintln!("Unknown command '{command}', :help lists the commands")
+            }
+            _ => eval(&mut engine, entry, color),
+        }
+    }
+}
+
+/// Reads lines until every `(`, `[` and `{` is closed, so a function or
+/// block can span several lines. `None` at the end of input.
+fn read_entry(lines: &mut impl Iterator<Item = io::Result<String>>) -> Option<String> {
+    let mut entry = String::new();
+    loop {
+        print!("{}", if entry.is_empty() { "> " } else { "... " });
+        let _ = io::stdout().flush();
+
+        match lines.next() {
+            Some(Ok(line)) => {
+                entry.push_str(&line);
+                entry.push('\n');
+            }
+            // finish a pending entry before leaving
+            _ if entry.is_empty() => return None,
+            _ => return Some(entry),
+        }
+
+        // meta commands are always a single line
+        if entry.starts_with(':') || depth(&entry) <= 0 {
+            return Some(entry);
+        }
+    }
+}
+
+/// How many brackets of any kind are still open. Source that does not scan
+/// counts as complete, so the error gets reported.
+fn depth(source: &str) -> i32 {
+    let mut scanner = Scanner::new(source);
+    if scanner.scan_tokens().is_err() {
+        return 0;
+    }
+
+    scanner
+        .tokens
+        .iter()
+        .map(|token| match token.token_type {
+            TokenType::Punctuation(
+                PunctuationKind::LPAREN | PunctuationKind::LBRACE | PunctuationKind::LCURL,
+            ) => 1,
+            TokenType::Punctuation(
+                PunctuationKind::RPAREN | PunctuationKind::RBRACE | PunctuationKind::RCURL,
+            ) => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn eval(engine: &mut Engine, entry: &str, color: bool) {
+    let source = terminate(entry);
+    let program = match parse(&source) {
+        Ok(program) => program,
+        Err(errors) => return report(&source, &errors, color),
+    };
+    match engine.check(&program) {
+        Ok(warnings) => report(&source, &warnings, color),
+        Err(errors) => return report(&source, &errors, color),
+    }
+
+    match engine.execute(&program) {
+        Ok(Value::Nil) => {}
+        Ok(value) => println!("{value}"),
+        Err(e) => report(&source, &[e], color),
+    }
+}
+
+/// Lets an expression be typed without its closing `;`.
+fn terminate(entry: &str) -> String {
+    let entry = entry.trim_end();
+    match entry.ends_with(';') || entry.ends_with('}') {
+        true => entry.to_string(),
+        false => format!("{entry};"),
+    }
+}
+
+fn report(source: &str, diagnostics: &[CompilerError], color: bool) {
+    let renderer = Renderer::new(source, "<repl>").with_color(color);
+    for e in diagnostics.iter() {
+        eprintln!("{}", renderer.render(e));
+    }
+}
@@ -0,0 +1,178 @@
+use std::rc::Rc;
+
+use error::types::Span;
+use interpreter::value::Value;
+use parser::types::{Function, Node, Operator, Pattern, Type};
+
+/// Where a variable lives. Locals no inner function can see get a slot in
+/// their frame, everything else is bound by name in an `Environment`, so
+/// closures and methods see the same scopes as under the tree-walker.
+#[derive(Debug, Clone, Copy)]
+pub enum Variable {
+    Slot(usize),
+    /// Index into `Chunk::names`.
+    Env(usize),
+}
+
+/// A single instruction. Operands index the pools of the chunk they belong
+/// to, jumps hold the position of their target in `Chunk::code`.
+#[derive(Debug, Clone)]
+pub enum Op {
+    /// Pushes `constants[i]`.
+    Constant(usize),
+    Nil,
+    Pop,
+    /// Pushes a copy of the top value.
+    Dup,
+    /// Pushes a copy of the top two values, keeping their order.
+    Dup2,
+    GetSlot(usize),
+    /// Stores the top value in a slot, leaving it on the stack.
+    SetSlot(usize),
+    /// Looks a name up in the current scope chain.
+    GetName(usize),
+    /// Binds the popped value to a name in the current scope, immutably.
+    DefineName(usize),
+    /// `let` of a variable bound by name, the popped value is checked
+    /// against the lock of `bindings[i]`.
+    Declare(usize),
+    /// Rebinds the name of `assignments[i]`, leaving the value on the stack.
+    AssignName(usize),
+    /// Fails with the assignment of an immutable slot, named by `names[i]`.
+    Immutable(usize),
+    This,
+    Binary(Operator),
+    Negate,
+    Not,
+    /// Replaces the top value with whether it is truthy.
+    Truthy,
+    Jump(usize),
+    /// Pops the top value and jumps when it is falsy.
+    JumpIfFalse(usize),
+    /// Jumps when the top value is nil, leaving it on the stack.
+    JumpIfNil(usize),
+    /// Jumps when the top value is not nil, otherwise pops it.
+    JumpUnlessNil(usize),
+    Array(usize),
+    Tuple(usize),
+    /// Builds a map from `n` key value pairs.
+    Map(usize),
+    Index,
+    /// Pops a value, an index and an object, pushing the value back.
+    SetIndex,
+    GetField(usize),
+    /// Pops a value and an object, pushing the value back.
+    SetField(usize),
+    /// `base::member`, both indexing `names`.
+    Path(usize, usize),
+    /// Builds `literals[i]` from the values of its given fields.
+    Literal(usize),
+    /// A named function declared in the current scope.
+    Function(usize),
+    /// A closure over the current scope, or over a copy of it with `move`.
+    Closure(usize, bool),
+    /// Declares the struct, interface, impl or enum `items[i]`.
+    Item(usize),
+    Call(usize),
+    Return,
+    /// `value?`
+    Try,
+    PushScope,
+    PopScope,
+    /// Marks the start of a loop, so `break` and `continue` can restore the
+    /// stack, scopes and iterators it started with.
+    Loop,
+    EndLoop,
+    Break(usize),
+    Continue(usize),
+    /// Pops an iterable and starts iterating over it.
+    Iter,
+    /// Pushes the next item, or jumps once the iterator is exhausted.
+    Next(usize),
+    EndIter,
+    /// Matches the value in a slot against `patterns[i]`, binding its names
+    /// and pushing whether it matched.
+    Match(usize, usize),
+    /// Fails because no arm matched the value in a slot.
+    NoMatch(usize),
+    /// Fails with the message `names[i]`.
+    Fail(usize),
+}
+
+/// Lock and mutability of a `let` bound by name.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub name: String,
+    pub mutable: bool,
+    pub annotation: Option<Type>,
+    pub inferred: bool,
+}
+
+/// Assignment to a variable bound by name, with the spans its errors point at.
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub name: String,
+    pub target: Span,
+    pub value: Span,
+}
+
+/// A struct literal, or a struct like variant with `variant`. The values of
+/// `fields` are on the stack in the same order.
+#[derive(Debug, Clone)]
+pub struct Literal {
+    pub name: String,
+    pub variant: Option<String>,
+    pub fields: Vec<(String, Span)>,
+}
+
+/// A match arm's pattern and where each of the names it binds lives.
+#[derive(Debug, Clone)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub bindings: Vec<(String, Variable)>,
+}
+
+/// Compiled code of a function body, or of a whole program. Every
+/// instruction has the span of the node it came from for errors.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub functions: Vec<Rc<Function>>,
+    pub items: Vec<Node>,
+    pub bindings: Vec<Binding>,
+    pub assignments: Vec<Assignment>,
+    pub literals: Vec<Literal>,
+    pub patterns: Vec<Arm>,
+    /// Slots a frame running the chunk needs.
+    pub slots: usize,
+    /// Where each parameter is bound on a call.
+    pub params: Vec<Variable>,
+    /// Whether a call needs a scope of its own, for the names it binds.
+    pub scoped: bool,
+}
+
+impl Chunk {
+    pub fn emit(&mut self, op: Op, span: Span) -> usize {
+        self.code.push(op);
+        self.spans.push(span);
+        self.code.len() - 1
+    }
+
+    pub fn constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    pub fn name(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        }
+    }
+}
@@ -0,0 +1,739 @@
+use std::{collections::HashSet, rc::Rc};
+
+use error::types::Span;
+use interpreter::{interpreter::literal_value, value::Value};
+use parser::types::{
+    Block, Declaration, Function, MatchArm, Node, NodeKind, Operator, Pattern, PatternKind,
+    Program, UnaryOperator, VariantPattern,
+};
+
+use crate::chunk::{Arm, Assignment, Binding, Chunk, Literal, Op, Variable};
+
+/// Compiles one function body, or a whole program, into a `Chunk`. Nested
+/// functions are compiled separately, the first time they are called.
+pub struct Compiler {
+    chunk: Chunk,
+    /// Names used by functions nested in the code being compiled. Those have
+    /// to be bound by name, so the inner functions can find them.
+    captured: HashSet<String>,
+    scopes: Vec<Scope>,
+    loops: Vec<Loop>,
+    next_slot: usize,
+}
+
+/// Variables declared in a block, and the first slot the block could use.
+struct Scope {
+    locals: Vec<Local>,
+    first_slot: usize,
+}
+
+struct Local {
+    name: String,
+    variable: Variable,
+    mutable: bool,
+}
+
+/// A loop being compiled, with the `break`s to patch once its end is known.
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+}
+
+impl Compiler {
+    fn new<'a>(nodes: impl Iterator<Item = &'a Node>) -> Compiler {
+        let mut captured = HashSet::new();
+        for node in nodes {
+            nested_names(node, &mut captured);
+        }
+        Compiler {
+            chunk: Chunk::default(),
+            captured,
+            scopes: vec![Scope {
+                locals: vec![],
+                first_slot: 0,
+            }],
+            loops: vec![],
+            next_slot: 0,
+        }
+    }
+
+    /// The top level of a program, which runs in the global scope. Functions
+    /// are defined first, so they can be called before their declaration.
+    pub fn script(program: &Program) -> Chunk {
+        let mut compiler = Compiler::new(program.tree.iter());
+        for node in program.tree.iter() {
+            if let NodeKind::Function(function) = &node.kind {
+                compiler.function_declaration(function, node.span);
+            }
+        }
+        for (i, node) in program.tree.iter().enumerate() {
+            if i > 0 {
+                compiler.chunk.emit(Op::Pop, node.span);
+            }
+            match node.kind {
+                NodeKind::Function(_) => {
+                    compiler.chunk.emit(Op::Nil, node.span);
+                }
+                _ => compiler.expression(node),
+            }
+        }
+        if program.tree.is_empty() {
+            compiler.chunk.emit(Op::Nil, Span::default());
+        }
+        compiler.finish()
+    }
+
+    /// The body of a function, which runs in a scope nested in its closure.
+    pub fn function(function: &Function) -> Chunk {
+        let body = function
+            .body
+            .as_ref()
+            .expect("only functions with a body are called");
+        let mut compiler = Compiler::new(block_nodes(body));
+        for param in function.params.iter() {
+            let variable = compiler.variable(&param.name, false, false);
+            compiler.chunk.params.push(variable);
+        }
+        compiler.chunk.scoped = compiler.needs_scope(body)
+            || function
+                .params
+                .iter()
+                .any(|p| compiler.captured.contains(&p.name));
+        compiler.block_body(body);
+        compiler.finish()
+    }
+
+    /// A single expression, such as the default value of a field.
+    pub fn expression_chunk(node: &Node) -> Chunk {
+        let mut compiler = Compiler::new(std::iter::once(node));
+        compiler.expression(node);
+        compiler.finish()
+    }
+
+    fn finish(mut self) -> Chunk {
+        self.chunk.emit(Op::Return, Span::default());
+        self.chunk
+    }
+
+    fn expression(&mut self, node: &Node) {
+        let span = node.span;
+        match &node.kind {
+            NodeKind::Literal(literal) => {
+                let i = self.chunk.constant(literal_value(literal));
+                self.chunk.emit(Op::Constant(i), span);
+            }
+            NodeKind::Name(name) => self.get(name, span),
+            NodeKind::Declaration(declaration) => {
+                self.expression(&declaration.value);
+                self.declare(declaration);
+                self.chunk.emit(Op::Nil, span);
+            }
+            NodeKind::BinaryExpr { op, lhs, rhs } => self.binary(op, lhs, rhs, span),
+            NodeKind::UnaryExpr { op, operand } => {
+                self.expression(operand);
+                let op = match op {
+                    UnaryOperator::Negate => Op::Negate,
+                    UnaryOperator::Not => Op::Not,
+                };
+                self.chunk.emit(op, span);
+            }
+            NodeKind::Function(function) => {
+                self.function_declaration(function, span);
+                self.chunk.emit(Op::Nil, span);
+            }
+            NodeKind::Closure { function, by_value } => {
+                let i = self.function_index(function);
+                self.chunk.emit(Op::Closure(i, *by_value), span);
+            }
+            NodeKind::Call { callee, args } => {
+                // `a?.method()` skips the call and its arguments when `a` is nil
+                let skip = match &callee.kind {
+                    NodeKind::Get {
+                        object,
+                        field,
+                        optional: true,
+                    } => {
+                        self.expression(object);
+                        let skip = self.chunk.emit(Op::JumpIfNil(0), callee.span);
+                        let field = self.chunk.name(field);
+                        self.chunk.emit(Op::GetField(field), callee.span);
+                        Some(skip)
+                    }
+                    _ => {
+                        self.expression(callee);
+                        None
+                    }
+                };
+                for arg in args.iter() {
+                    self.expression(arg);
+                }
+                self.chunk.emit(Op::Call(args.len()), span);
+                if let Some(skip) = skip {
+                    self.patch(skip);
+                }
+            }
+            NodeKind::Return(value) => {
+                match value {
+                    Some(value) => self.expression(value),
+                    None => {
+                        self.chunk.emit(Op::Nil, span);
+                    }
+                }
+                self.chunk.emit(Op::Return, span);
+            }
+            NodeKind::Struct(_) | NodeKind::Shared(_) | NodeKind::Impl(_) | NodeKind::Enum(_) => {
+                self.chunk.items.push(node.clone());
+                let i = self.chunk.items.len() - 1;
+                self.chunk.emit(Op::Item(i), span);
+            }
+            NodeKind::Block(block) => self.block(block, span),
+            NodeKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expression(condition);
+                let skip_then = self.chunk.emit(Op::JumpIfFalse(0), span);
+                self.block(then, span);
+                let skip_else = self.chunk.emit(Op::Jump(0), span);
+                self.patch(skip_then);
+                match otherwise {
+                    Some(otherwise) => self.expression(otherwise),
+                    None => {
+                        self.chunk.emit(Op::Nil, span);
+                    }
+                }
+                self.patch(skip_else);
+            }
+            NodeKind::While { condition, body } => {
+                self.chunk.emit(Op::Loop, span);
+                let start = self.chunk.code.len();
+                self.expression(condition);
+                let exit = self.chunk.emit(Op::JumpIfFalse(0), span);
+                self.loop_body(start, span, |compiler| {
+                    compiler.block(body, span);
+                    compiler.chunk.emit(Op::Pop, span);
+                });
+                self.patch(exit);
+                self.chunk.emit(Op::EndLoop, span);
+                self.chunk.emit(Op::Nil, span);
+            }
+            NodeKind::For {
+                binding,
+                iterable,
+                body,
+            } => {
+                self.expression(iterable);
+                self.chunk.emit(Op::Iter, iterable.span);
+                self.chunk.emit(Op::Loop, span);
+                let start = self.chunk.code.len();
+                let exit = self.chunk.emit(Op::Next(0), span);
+                // each iteration binds the item in a scope shared with the body
+                let scoped = self.captured.contains(binding) || self.needs_scope(body);
+                self.loop_body(start, span, |compiler| {
+                    if scoped {
+                        compiler.chunk.emit(Op::PushScope, span);
+                    }
+                    compiler.begin_scope();
+                    let variable = compiler.variable(binding, false, false);
+                    compiler.bind(variable, span);
+                    compiler.block_body(body);
+                    compiler.chunk.emit(Op::Pop, span);
+                    compiler.end_scope();
+                    if scoped {
+                        compiler.chunk.emit(Op::PopScope, span);
+                    }
+                });
+                self.patch(exit);
+                self.chunk.emit(Op::EndLoop, span);
+                self.chunk.emit(Op::EndIter, span);
+                self.chunk.emit(Op::Nil, span);
+            }
+            NodeKind::Match { scrutinee, arms } => self.match_arms(scrutinee, arms, span),
+            NodeKind::Break if self.loops.is_empty() => {
+                self.fail("`break` outside of a loop", span)
+            }
+            NodeKind::Break => {
+                let jump = self.chunk.emit(Op::Break(0), span);
+                if let Some(l) = self.loops.last_mut() {
+                    l.breaks.push(jump);
+                }
+            }
+            NodeKind::Continue => match self.loops.last() {
+                Some(l) => {
+                    let start = l.start;
+                    self.chunk.emit(Op::Continue(start), span);
+                }
+                None => self.fail("`continue` outside of a loop", span),
+            },
+            NodeKind::StructLiteral {
+                name,
+                variant,
+                fields,
+            } => {
+                for (_, value) in fields.iter() {
+                    self.expression(value);
+                }
+                self.chunk.literals.push(Literal {
+                    name: name.clone(),
+                    variant: variant.clone(),
+                    fields: fields.iter().map(|(n, v)| (n.clone(), v.span)).collect(),
+                });
+                let i = self.chunk.literals.len() - 1;
+                self.chunk.emit(Op::Literal(i), span);
+            }
+            NodeKind::Path { base, member } => {
+                let base = self.chunk.name(base);
+                let member = self.chunk.name(member);
+                self.chunk.emit(Op::Path(base, member), span);
+            }
+            NodeKind::Get {
+                object,
+                field,
+                optional,
+            } => {
+                self.expression(object);
+                let skip = optional.then(|| self.chunk.emit(Op::JumpIfNil(0), span));
+                let field = self.chunk.name(field);
+                self.chunk.emit(Op::GetField(field), span);
+                if let Some(skip) = skip {
+                    self.patch(skip);
+                }
+            }
+            NodeKind::Array(items) => {
+                for item in items.iter() {
+                    self.expression(item);
+                }
+                self.chunk.emit(Op::Array(items.len()), span);
+            }
+            NodeKind::Tuple(items) => {
+                for item in items.iter() {
+                    self.expression(item);
+                }
+                self.chunk.emit(Op::Tuple(items.len()), span);
+            }
+            NodeKind::Map(entries) => {
+                for (key, value) in entries.iter() {
+                    self.expression(key);
+                    self.expression(value);
+                }
+                self.chunk.emit(Op::Map(entries.len()), span);
+            }
+            NodeKind::Index { object, index } => {
+                self.expression(object);
+                self.expression(index);
+                self.chunk.emit(Op::Index, index.span);
+            }
+            NodeKind::Try(value) => {
+                self.expression(value);
+                self.chunk.emit(Op::Try, span);
+            }
+            NodeKind::Assign { target, op, value } => self.assign(target, op, value, span),
+            NodeKind::This => {
+                self.chunk.emit(Op::This, span);
+            }
+        }
+    }
+
+    fn binary(&mut self, op: &Operator, lhs: &Node, rhs: &Node, span: Span) {
+        // logical operators short circuit, so the right side is evaluated lazily
+        match op {
+            Operator::And => {
+                self.expression(lhs);
+                let short = self.chunk.emit(Op::JumpIfFalse(0), span);
+                self.expression(rhs);
+                self.chunk.emit(Op::Truthy, span);
+                let end = self.chunk.emit(Op::Jump(0), span);
+                self.patch(short);
+                let i = self.chunk.constant(Value::Boolean(false));
+                self.chunk.emit(Op::Constant(i), span);
+                self.patch(end);
+            }
+            Operator::Or => {
+                self.expression(lhs);
+                let long = self.chunk.emit(Op::JumpIfFalse(0), span);
+                let i = self.chunk.constant(Value::Boolean(true));
+                self.chunk.emit(Op::Constant(i), span);
+                let end = self.chunk.emit(Op::Jump(0), span);
+                self.patch(long);
+                self.expression(rhs);
+                self.chunk.emit(Op::Truthy, span);
+                self.patch(end);
+            }
+            Operator::Coalesce => {
+                self.expression(lhs);
+                let end = self.chunk.emit(Op::JumpUnlessNil(0), span);
+                self.expression(rhs);
+                self.patch(end);
+            }
+            op => {
+                self.expression(lhs);
+                self.expression(rhs);
+                self.chunk.emit(Op::Binary(op.clone()), span);
+            }
+        }
+    }
+
+    /// `target = value` or `target += value`, mirroring the tree-walker's
+    /// order: the current value is read before the new one is evaluated.
+    fn assign(&mut self, target: &Node, op: &Option<Operator>, value: &Node, span: Span) {
+        match &target.kind {
+            NodeKind::Name(name) => match self.resolve(name) {
+                Some(Local {
+                    variable: Variable::Slot(slot),
+                    mutable,
+                    ..
+                }) => {
+                    let (slot, mutable) = (*slot, *mutable);
+                    if op.is_some() {
+                        self.chunk.emit(Op::GetSlot(slot), target.span);
+                    }
+                    self.expression(value);
+                    self.compound(op, span);
+                    match mutable {
+                        true => self.chunk.emit(Op::SetSlot(slot), span),
+                        false => {
+                            let name = self.chunk.name(name);
+                            self.chunk.emit(Op::Immutable(name), span)
+                        }
+                    };
+                }
+                _ => {
+                    if op.is_some() {
+                        self.get(name, target.span);
+                    }
+                    self.expression(value);
+                    self.compound(op, span);
+                    self.chunk.assignments.push(Assignment {
+                        name: name.clone(),
+                        target: target.span,
+                        value: value.span,
+                    });
+                    let i = self.chunk.assignments.len() - 1;
+                    self.chunk.emit(Op::AssignName(i), span);
+                }
+            },
+            NodeKind::Get { object, field, .. } => {
+                self.expression(object);
+                let field = self.chunk.name(field);
+                if op.is_some() {
+                    self.chunk.emit(Op::Dup, target.span);
+                    self.chunk.emit(Op::GetField(field), target.span);
+                }
+                self.expression(value);
+                self.compound(op, span);
+                self.chunk.emit(Op::SetField(field), target.span);
+            }
+            NodeKind::Index { object, index } => {
+                self.expression(object);
+                self.expression(index);
+                if op.is_some() {
+                    self.chunk.emit(Op::Dup2, index.span);
+                    self.chunk.emit(Op::Index, index.span);
+                }
+                self.expression(value);
+                self.compound(op, span);
+                self.chunk.emit(Op::SetIndex, index.span);
+            }
+            _ => self.fail("Invalid assignment target", target.span),
+        }
+    }
+
+    fn compound(&mut self, op: &Option<Operator>, span: Span) {
+        if let Some(op) = op {
+            self.chunk.emit(Op::Binary(op.clone()), span);
+        }
+    }
+
+    /// Pattern bindings live in a scope of their own, shared by guard and
+    /// body. The scrutinee is kept in a slot while the arms are tried.
+    fn match_arms(&mut self, scrutinee: &Node, arms: &[MatchArm], span: Span) {
+        self.expression(scrutinee);
+        self.begin_scope();
+        let value = self.slot();
+        self.chunk.emit(Op::SetSlot(value), span);
+        self.chunk.emit(Op::Pop, span);
+
+        let mut ends = vec![];
+        for arm in arms.iter() {
+            let mut names = vec![];
+            pattern_names(&arm.pattern, &mut names);
+            let scoped = names.iter().any(|n| self.captured.contains(n));
+            if scoped {
+                self.chunk.emit(Op::PushScope, arm.pattern.span);
+            }
+            self.begin_scope();
+            let bindings = names
+                .iter()
+                .map(|name| (name.clone(), self.variable(name, false, false)))
+                .collect();
+            self.chunk.patterns.push(Arm {
+                pattern: arm.pattern.clone(),
+                bindings,
+            });
+            let i = self.chunk.patterns.len() - 1;
+            self.chunk.emit(Op::Match(i, value), arm.pattern.span);
+            let mut skips = vec![self.chunk.emit(Op::JumpIfFalse(0), arm.pattern.span)];
+            if let Some(guard) = &arm.guard {
+                self.expression(guard);
+                skips.push(self.chunk.emit(Op::JumpIfFalse(0), guard.span));
+            }
+            self.expression(&arm.body);
+            if scoped {
+                self.chunk.emit(Op::PopScope, arm.body.span);
+            }
+            ends.push(self.chunk.emit(Op::Jump(0), arm.body.span));
+            for skip in skips {
+                self.patch(skip);
+            }
+            if scoped {
+                self.chunk.emit(Op::PopScope, arm.body.span);
+            }
+            self.end_scope();
+        }
+
+        self.chunk.emit(Op::NoMatch(value), span);
+        for end in ends {
+            self.patch(end);
+        }
+        self.end_scope();
+    }
+
+    /// A block in a scope of its own. A scope only exists at runtime when
+    /// the block binds names there.
+    fn block(&mut self, block: &Block, span: Span) {
+        let scoped = self.needs_scope(block);
+        if scoped {
+            self.chunk.emit(Op::PushScope, span);
+        }
+        self.begin_scope();
+        self.block_body(block);
+        self.end_scope();
+        if scoped {
+            self.chunk.emit(Op::PopScope, span);
+        }
+    }
+
+    fn block_body(&mut self, block: &Block) {
+        for node in block.body.iter() {
+            self.expression(node);
+            self.chunk.emit(Op::Pop, node.span);
+        }
+        match &block.value {
+            Some(value) => self.expression(value),
+            None => {
+                self.chunk.emit(Op::Nil, Span::default());
+            }
+        }
+    }
+
+    /// Compiles the body of a loop starting at `start`, then jumps back to it.
+    fn loop_body(&mut self, start: usize, span: Span, body: impl FnOnce(&mut Compiler)) {
+        self.loops.push(Loop {
+            start,
+            breaks: vec![],
+        });
+        body(self);
+        self.chunk.emit(Op::Jump(start), span);
+        let done = self.loops.pop().expect("the loop was just pushed");
+        for jump in done.breaks {
+            self.patch(jump);
+        }
+    }
+
+    /// Whether a block binds any name in its own scope: a `let` that is
+    /// bound by name, a captured function, or a declared type.
+    fn needs_scope(&self, block: &Block) -> bool {
+        block_nodes(block).any(|node| match &node.kind {
+            NodeKind::Declaration(declaration) => self.by_name(declaration),
+            NodeKind::Function(function) => self.captured.contains(&function.name),
+            NodeKind::Struct(_) | NodeKind::Shared(_) | NodeKind::Enum(_) => true,
+            _ => false,
+        })
+    }
+
+    /// Variables that inner functions use, or whose type is locked, are
+    /// bound by name. The environment already keeps the lock.
+    fn by_name(&self, declaration: &Declaration) -> bool {
+        self.captured.contains(&declaration.symbol)
+            || declaration.annotation.is_some()
+            || declaration.inferred
+    }
+
+    fn declare(&mut self, declaration: &Declaration) {
+        let span = declaration.value.span;
+        if !self.by_name(declaration) {
+            let variable = self.variable(&declaration.symbol, declaration.mutable, false);
+            self.bind(variable, span);
+            return;
+        }
+
+        self.chunk.bindings.push(Binding {
+            name: declaration.symbol.clone(),
+            mutable: declaration.mutable,
+            annotation: declaration.annotation.clone(),
+            inferred: declaration.inferred,
+        });
+        let i = self.chunk.bindings.len() - 1;
+        self.chunk.emit(Op::Declare(i), span);
+        self.variable(&declaration.symbol, declaration.mutable, true);
+    }
+
+    /// Adds a variable to the innermost scope. `by_name` forces it out of a
+    /// slot, otherwise only captured names are bound by name.
+    fn variable(&mut self, name: &str, mutable: bool, by_name: bool) -> Variable {
+        let variable = match by_name || self.captured.contains(name) {
+            true => Variable::Env(self.chunk.name(name)),
+            false => Variable::Slot(self.slot()),
+        };
+        let scope = self.scopes.last_mut().expect("there is always a scope");
+        scope.locals.push(Local {
+            name: name.to_string(),
+            variable,
+            mutable,
+        });
+        variable
+    }
+
+    /// Pops the top value into a variable.
+    fn bind(&mut self, variable: Variable, span: Span) {
+        match variable {
+            Variable::Slot(slot) => {
+                self.chunk.emit(Op::SetSlot(slot), span);
+                self.chunk.emit(Op::Pop, span);
+            }
+            Variable::Env(name) => {
+                self.chunk.emit(Op::DefineName(name), span);
+            }
+        }
+    }
+
+    fn get(&mut self, name: &str, span: Span) {
+        let op = match self.resolve(name) {
+            Some(Local {
+                variable: Variable::Slot(slot),
+                ..
+            }) => Op::GetSlot(*slot),
+            _ => Op::GetName(self.chunk.name(name)),
+        };
+        self.chunk.emit(op, span);
+    }
+
+    fn resolve(&self, name: &str) -> Option<&Local> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.locals.iter().rev().find(|l| l.name == name))
+    }
+
+    fn slot(&mut self) -> usize {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.chunk.slots = self.chunk.slots.max(self.next_slot);
+        slot
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Scope {
+            locals: vec![],
+            first_slot: self.next_slot,
+        });
+    }
+
+    /// Leaves the innermost scope, freeing its slots for later scopes.
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().expect("there is always a scope");
+        self.next_slot = scope.first_slot;
+    }
+
+    /// Binds a named function to a new closure over the current scope.
+    fn function_declaration(&mut self, function: &Rc<Function>, span: Span) {
+        let i = self.function_index(function);
+        self.chunk.emit(Op::Function(i), span);
+        let variable = self.variable(&function.name, false, false);
+        self.bind(variable, span);
+    }
+
+    fn function_index(&mut self, function: &Rc<Function>) -> usize {
+        self.chunk.functions.push(function.clone());
+        self.chunk.functions.len() - 1
+    }
+
+    fn fail(&mut self, message: &str, span: Span) {
+        let i = self.chunk.name(message);
+        self.chunk.emit(Op::Fail(i), span);
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.code.len();
+        match &mut self.chunk.code[at] {
+            Op::Jump(to)
+            | Op::JumpIfFalse(to)
+            | Op::JumpIfNil(to)
+            | Op::JumpUnlessNil(to)
+            | Op::Break(to)
+            | Op::Next(to) => *to = target,
+            op => unreachable!("{op:?} is not a jump"),
+        }
+    }
+}
+
+fn block_nodes(block: &Block) -> impl Iterator<Item = &Node> {
+    block.body.iter().chain(block.value.as_deref())
+}
+
+/// Collects every name used inside a function, method or field default
+/// nested somewhere in `node`.
+fn nested_names(node: &Node, names: &mut HashSet<String>) {
+    match &node.kind {
+        NodeKind::Function(_)
+        | NodeKind::Closure { .. }
+        | NodeKind::Struct(_)
+        | NodeKind::Shared(_)
+        | NodeKind::Impl(_) => used_names(node, names),
+        _ => {
+            for child in node.children() {
+                nested_names(child, names);
+            }
+        }
+    }
+}
+
+fn used_names(node: &Node, names: &mut HashSet<String>) {
+    if let NodeKind::Name(name) = &node.kind {
+        names.insert(name.clone());
+    }
+    for child in node.children() {
+        used_names(child, names);
+    }
+}
+
+/// Names a pattern binds, in the order it binds them.
+fn pattern_names(pattern: &Pattern, names: &mut Vec<String>) {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Literal(_) | PatternKind::Range(..) => {}
+        PatternKind::Binding(name) => names.push(name.clone()),
+        PatternKind::Struct { fields, .. }
+        | PatternKind::Variant {
+            fields: VariantPattern::Struct(fields),
+            ..
+        } => {
+            for (_, pattern) in fields.iter() {
+                pattern_names(pattern, names);
+            }
+        }
+        PatternKind::Variant {
+            fields: VariantPattern::Tuple(patterns),
+            ..
+        } => {
+            for pattern in patterns.iter() {
+                pattern_names(pattern, names);
+            }
+        }
+        PatternKind::Variant {
+            fields: VariantPattern::Unit,
+            ..
+        } => {}
+    }
+}
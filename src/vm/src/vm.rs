@@ -0,0 +1,556 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use error::types::{CompilerError, Span};
+use interpreter::{
+    environment::{Environment, TypeLock},
+    interpreter::{
+        assign_name, binary_op, check_args, check_return, construct, get, immutable, implement,
+        index_value, iterate, match_pattern, mismatch, path, runtime_error, set_field, set_index,
+        struct_literal, struct_type, variant_literal, Unwind, MAX_CALL_DEPTH,
+    },
+    native::Caller,
+    prelude,
+    value::{EnumType, FunctionValue, SharedType, Value},
+};
+use parser::types::{Function, Node, NodeKind, Program};
+
+use crate::{
+    chunk::{Chunk, Op, Variable},
+    compiler::Compiler,
+};
+
+/// Stack machine running compiled chunks. It shares its values and runtime
+/// rules with the tree-walking interpreter, so a program gives the same
+/// results on either.
+pub struct Vm {
+    globals: Rc<RefCell<Environment>>,
+    /// Chunks of the functions called so far. The declaration is kept
+    /// alive, so its address stays unique.
+    functions: HashMap<*const Function, (Rc<Function>, Rc<Chunk>)>,
+    /// Chunks of the field defaults evaluated so far, kept alive the same
+    /// way.
+    defaults: HashMap<*const Node, (Rc<Node>, Rc<Chunk>)>,
+    depth: usize,
+}
+
+/// State of a running chunk.
+struct Frame {
+    stack: Vec<Value>,
+    slots: Vec<Value>,
+    /// Scopes names are bound in, innermost last.
+    scopes: Vec<Rc<RefCell<Environment>>>,
+    iterators: Vec<Box<dyn Iterator<Item = Value>>>,
+    loops: Vec<Marker>,
+}
+
+/// What a loop started with, restored by `break` and `continue`.
+struct Marker {
+    stack: usize,
+    scopes: usize,
+    iterators: usize,
+}
+
+impl Frame {
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("the compiler keeps the stack balanced")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack
+            .last()
+            .expect("the compiler keeps the stack balanced")
+    }
+
+    /// Pops the top `n` values, in the order they were pushed.
+    fn pop_n(&mut self, n: usize) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - n)
+    }
+
+    fn env(&self) -> &Rc<RefCell<Environment>> {
+        self.scopes.last().expect("a frame always has a scope")
+    }
+
+    fn restore(&mut self) {
+        let marker = self
+            .loops
+            .last()
+            .expect("`break` is only compiled in loops");
+        self.stack.truncate(marker.stack);
+        self.scopes.truncate(marker.scopes);
+        self.iterators.truncate(marker.iterators);
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Caller for Vm {
+    fn call(&mut self, callee: Value, args: Vec<Value>, span: Span) -> Result<Value, Unwind> {
+        Vm::call(self, callee, args, span)
+    }
+}
+
+impl Vm {
+    /// A VM with the prelude of native functions installed.
+    pub fn new() -> Vm {
+        let mut globals = Environment::new();
+        prelude::install(&mut globals);
+        Vm {
+            globals: Rc::new(RefCell::new(globals)),
+            functions: HashMap::new(),
+            defaults: HashMap::new(),
+            depth: 0,
+        }
+    }
+
+    /// Compiles and runs a program, returning the value of its last node.
+    pub fn run(&mut self, program: &Program) -> Result<Value, CompilerError> {
+        let chunk = Compiler::script(program);
+        let slots = vec![Value::Nil; chunk.slots];
+        match self.execute(&chunk, slots, self.globals.clone()) {
+            Ok(v) | Err(Unwind::Return(v)) => Ok(v),
+            Err(Unwind::Error(e)) => Err(e),
+            Err(Unwind::Break(_) | Unwind::Continue(_)) => unreachable!(),
+        }
+    }
+
+    /// Calls a function value with already evaluated arguments in a fresh
+    /// frame, compiling the function the first time it is called.
+    pub fn call(&mut self, callee: Value, args: Vec<Value>, span: Span) -> Result<Value, Unwind> {
+        let function = match callee {
+            Value::Function(f) => f,
+            Value::Constructor(enum_type, tag) => return construct(enum_type, tag, args, span),
+            Value::Native(native) => return native.invoke(self, args, span),
+            v => {
+                return Err(runtime_error(
+                    span,
+                    format!("Cannot call a value of type {}", v.type_name()),
+                )
+                .into())
+            }
+        };
+
+        let declaration = &function.declaration;
+        if declaration.body.is_none() {
+            return Err(runtime_error(
+                span,
+                format!("Function '{}' has no body", declaration.name),
+            )
+            .into());
+        }
+        if args.len() != declaration.params.len() {
+            return Err(runtime_error(
+                span,
+                format!(
+                    "Function '{}' expects {} argument(s) but got {}",
+                    function.name(),
+                    declaration.params.len(),
+                    args.len()
+                ),
+            )
+            .into());
+        }
+
+        check_args(&function, &args, span)?;
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(runtime_error(span, "Stack overflow".to_string()).into());
+        }
+
+        let chunk = self.compiled(declaration);
+        let scope = match chunk.scoped {
+            true => Rc::new(RefCell::new(Environment::with_parent(
+                function.closure.clone(),
+            ))),
+            false => function.closure.clone(),
+        };
+        let mut slots = vec![Value::Nil; chunk.slots];
+        for (param, arg) in chunk.params.iter().zip(args) {
+            match param {
+                Variable::Slot(slot) => slots[*slot] = arg,
+                Variable::Env(name) => scope.borrow_mut().define(&chunk.names[*name], arg),
+            }
+        }
+
+        self.depth += 1;
+        let result = self.execute(&chunk, slots, scope);
+        self.depth -= 1;
+
+        let value = result.map_err(|e| match e {
+            Unwind::Error(e) => e.with_frame(&declaration.name, span).into(),
+            e => e,
+        })?;
+        check_return(&function, &value, span)?;
+        Ok(value)
+    }
+
+    fn compiled(&mut self, declaration: &Rc<Function>) -> Rc<Chunk> {
+        let (_, chunk) = self
+            .functions
+            .entry(Rc::as_ptr(declaration))
+            .or_insert_with(|| {
+                let chunk = Rc::new(Compiler::function(declaration));
+                (declaration.clone(), chunk)
+            });
+        chunk.clone()
+    }
+
+    /// Evaluates a field default in the scope it was declared in.
+    fn default(&mut self, node: &Rc<Node>, env: Rc<RefCell<Environment>>) -> Result<Value, Unwind> {
+        let (_, chunk) = self.defaults.entry(Rc::as_ptr(node)).or_insert_with(|| {
+            let chunk = Rc::new(Compiler::expression_chunk(node));
+            (node.clone(), chunk)
+        });
+        let chunk = chunk.clone();
+        let slots = vec![Value::Nil; chunk.slots];
+        self.execute(&chunk, slots, env)
+    }
+
+    fn execute(
+        &mut self,
+        chunk: &Chunk,
+        slots: Vec<Value>,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<Value, Unwind> {
+        let mut frame = Frame {
+            stack: vec![],
+            slots,
+            scopes: vec![env],
+            iterators: vec![],
+            loops: vec![],
+        };
+
+        let mut ip = 0;
+        loop {
+            let span = chunk.spans[ip];
+            let op = &chunk.code[ip];
+            ip += 1;
+            match op {
+                Op::Constant(i) => frame.stack.push(chunk.constants[*i].clone()),
+                Op::Nil => frame.stack.push(Value::Nil),
+                Op::Pop => {
+                    frame.pop();
+                }
+                Op::Dup => frame.stack.push(frame.peek().clone()),
+                Op::Dup2 => {
+                    let top = frame.stack[frame.stack.len() - 2..].to_vec();
+                    frame.stack.extend(top);
+                }
+                Op::GetSlot(slot) => frame.stack.push(frame.slots[*slot].clone()),
+                Op::SetSlot(slot) => frame.slots[*slot] = frame.peek().clone(),
+                Op::GetName(i) => {
+                    let name = &chunk.names[*i];
+                    let value = frame.env().borrow().get(name);
+                    match value {
+                        Some(v) => frame.stack.push(v),
+                        None => {
+                            return Err(
+                                runtime_error(span, format!("Undefined variable '{name}'")).into()
+                            )
+                        }
+                    }
+                }
+                Op::DefineName(i) => {
+                    let value = frame.pop();
+                    frame.env().borrow_mut().define(&chunk.names[*i], value);
+                }
+                Op::Declare(i) => {
+                    let binding = &chunk.bindings[*i];
+                    let value = frame.pop();
+                    let lock = TypeLock::new(binding.annotation.as_ref(), binding.inferred, &value);
+                    // the type checker only sees values whose type is known statically
+                    if let Some(lock) = &lock {
+                        if !lock.accepts(&value) {
+                            return Err(mismatch(lock, &binding.name, &value, span).into());
+                        }
+                    }
+                    frame
+                        .env()
+                        .borrow_mut()
+                        .declare(&binding.name, value, binding.mutable, lock);
+                }
+                Op::AssignName(i) => {
+                    let assignment = &chunk.assignments[*i];
+                    let value = frame.peek().clone();
+                    let (target, value_span) = (assignment.target, assignment.value);
+                    assign_name(
+                        frame.env(),
+                        &assignment.name,
+                        value,
+                        target,
+                        value_span,
+                        span,
+                    )?;
+                }
+                Op::Immutable(i) => return Err(immutable(&chunk.names[*i], span).into()),
+                Op::This => {
+                    let value = frame.env().borrow().get("this");
+                    match value {
+                        Some(v) => frame.stack.push(v),
+                        None => {
+                            return Err(runtime_error(
+                                span,
+                                "`this` can only be used inside a method".to_string(),
+                            )
+                            .into())
+                        }
+                    }
+                }
+                Op::Binary(op) => {
+                    let r = frame.pop();
+                    let l = frame.pop();
+                    let value = binary_op(op, l, r).map_err(|m| runtime_error(span, m))?;
+                    frame.stack.push(value);
+                }
+                Op::Negate => match frame.pop() {
+                    Value::Number(n) => frame.stack.push(Value::Number(-n)),
+                    v => {
+                        return Err(runtime_error(
+                            span,
+                            format!("Cannot negate a value of type {}", v.type_name()),
+                        )
+                        .into())
+                    }
+                },
+                Op::Not => {
+                    let value = frame.pop();
+                    frame.stack.push(Value::Boolean(!value.is_truthy()));
+                }
+                Op::Truthy => {
+                    let value = frame.pop();
+                    frame.stack.push(Value::Boolean(value.is_truthy()));
+                }
+                Op::Jump(to) => ip = *to,
+                Op::JumpIfFalse(to) => {
+                    if !frame.pop().is_truthy() {
+                        ip = *to;
+                    }
+                }
+                Op::JumpIfNil(to) => {
+                    if let Value::Nil = frame.peek() {
+                        ip = *to;
+                    }
+                }
+                Op::JumpUnlessNil(to) => match frame.peek() {
+                    Value::Nil => {
+                        frame.pop();
+                    }
+                    _ => ip = *to,
+                },
+                Op::Array(n) => {
+                    let items = frame.pop_n(*n);
+                    frame.stack.push(Value::Array(Rc::new(RefCell::new(items))));
+                }
+                Op::Tuple(n) => {
+                    let items = frame.pop_n(*n);
+                    frame.stack.push(Value::Tuple(Rc::new(items)));
+                }
+                Op::Map(n) => {
+                    let mut items = frame.pop_n(n * 2).into_iter();
+                    let mut map: Vec<(Value, Value)> = Vec::with_capacity(*n);
+                    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                        // a repeated key keeps the last value
+                        match map.iter_mut().find(|(k, _)| *k == key) {
+                            Some(entry) => entry.1 = value,
+                            None => map.push((key, value)),
+                        }
+                    }
+                    frame.stack.push(Value::Map(Rc::new(RefCell::new(map))));
+                }
+                Op::Index => {
+                    let index = frame.pop();
+                    let object = frame.pop();
+                    let value = index_value(&object, &index).map_err(|m| runtime_error(span, m))?;
+                    frame.stack.push(value);
+                }
+                Op::SetIndex => {
+                    let value = frame.pop();
+                    let index = frame.pop();
+                    let object = frame.pop();
+                    set_index(&object, &index, value.clone())
+                        .map_err(|m| runtime_error(span, m))?;
+                    frame.stack.push(value);
+                }
+                Op::GetField(i) => {
+                    let object = frame.pop();
+                    let value = get(&self.globals, object, &chunk.names[*i], span)?;
+                    frame.stack.push(value);
+                }
+                Op::SetField(i) => {
+                    let value = frame.pop();
+                    let object = frame.pop();
+                    set_field(&object, &chunk.names[*i], value.clone())
+                        .map_err(|m| runtime_error(span, m))?;
+                    frame.stack.push(value);
+                }
+                Op::Path(base, member) => {
+                    let value = path(
+                        frame.env(),
+                        &chunk.names[*base],
+                        &chunk.names[*member],
+                        span,
+                    )?;
+                    frame.stack.push(value);
+                }
+                Op::Literal(i) => {
+                    let literal = &chunk.literals[*i];
+                    let values = frame.pop_n(literal.fields.len());
+                    let given = literal
+                        .fields
+                        .iter()
+                        .zip(values)
+                        .map(|((name, span), value)| (name.clone(), value, *span))
+                        .collect();
+                    let env = frame.env().clone();
+                    let value = match &literal.variant {
+                        Some(variant) => variant_literal(&env, &literal.name, variant, given, span),
+                        None => {
+                            struct_literal(&env, &literal.name, given, span, &mut |node, scope| {
+                                self.default(node, scope.clone())
+                            })
+                        }
+                    }?;
+                    frame.stack.push(value);
+                }
+                Op::Function(i) => frame.stack.push(Value::Function(Rc::new(FunctionValue {
+                    declaration: chunk.functions[*i].clone(),
+                    closure: frame.env().clone(),
+                }))),
+                Op::Closure(i, by_value) => {
+                    let closure = match by_value {
+                        true => Rc::new(RefCell::new(frame.env().borrow().snapshot())),
+                        false => frame.env().clone(),
+                    };
+                    frame.stack.push(Value::Function(Rc::new(FunctionValue {
+                        declaration: chunk.functions[*i].clone(),
+                        closure,
+                    })));
+                }
+                Op::Item(i) => {
+                    self.item(&chunk.items[*i], frame.env())?;
+                    frame.stack.push(Value::Nil);
+                }
+                Op::Call(n) => {
+                    let args = frame.pop_n(*n);
+                    let callee = frame.pop();
+                    let value = self.call(callee, args, span)?;
+                    frame.stack.push(value);
+                }
+                Op::Return => return Ok(frame.pop()),
+                Op::Try => {
+                    let value = frame.pop();
+                    match value.as_result() {
+                        Some(Ok(v)) => {
+                            let v = v.clone();
+                            frame.stack.push(v);
+                        }
+                        // an `Err` returns from the current function as is
+                        Some(Err(_)) if self.depth > 0 => return Ok(value),
+                        Some(Err(e)) => {
+                            return Err(runtime_error(span, format!("Unhandled error: {e}"))
+                                .with_help(
+                                    "`?` outside of a function has nowhere to return the error to",
+                                )
+                                .into())
+                        }
+                        None => {
+                            return Err(runtime_error(
+                                span,
+                                format!("`?` expects a Result, found {}", value.type_name()),
+                            )
+                            .into())
+                        }
+                    }
+                }
+                Op::PushScope => {
+                    let scope = Environment::with_parent(frame.env().clone());
+                    frame.scopes.push(Rc::new(RefCell::new(scope)));
+                }
+                Op::PopScope => {
+                    frame.scopes.pop();
+                }
+                Op::Loop => frame.loops.push(Marker {
+                    stack: frame.stack.len(),
+                    scopes: frame.scopes.len(),
+                    iterators: frame.iterators.len(),
+                }),
+                Op::EndLoop => {
+                    frame.loops.pop();
+                }
+                Op::Break(to) | Op::Continue(to) => {
+                    frame.restore();
+                    ip = *to;
+                }
+                Op::Iter => {
+                    let iterable = frame.pop();
+                    let items = iterate(&iterable).map_err(|m| runtime_error(span, m))?;
+                    frame.iterators.push(items);
+                }
+                Op::Next(to) => {
+                    let iterator = frame.iterators.last_mut().expect("`Next` follows `Iter`");
+                    match iterator.next() {
+                        Some(item) => frame.stack.push(item),
+                        None => ip = *to,
+                    }
+                }
+                Op::EndIter => {
+                    frame.iterators.pop();
+                }
+                Op::Match(i, slot) => {
+                    let arm = &chunk.patterns[*i];
+                    let mut bindings = vec![];
+                    let matched = match_pattern(&arm.pattern, &frame.slots[*slot], &mut bindings);
+                    if matched {
+                        for (name, value) in bindings {
+                            let variable = arm.bindings.iter().rev().find(|(n, _)| *n == name);
+                            match variable {
+                                Some((_, Variable::Slot(slot))) => frame.slots[*slot] = value,
+                                _ => frame.env().borrow_mut().define(&name, value),
+                            }
+                        }
+                    }
+                    frame.stack.push(Value::Boolean(matched));
+                }
+                Op::NoMatch(slot) => {
+                    return Err(runtime_error(
+                        span,
+                        format!("No match arm matches {}", frame.slots[*slot].repr()),
+                    )
+                    .into())
+                }
+                Op::Fail(i) => return Err(runtime_error(span, chunk.names[*i].clone()).into()),
+            }
+        }
+    }
+
+    /// Declares a struct, interface, impl or enum in `env`.
+    fn item(&mut self, node: &Node, env: &Rc<RefCell<Environment>>) -> Result<(), Unwind> {
+        let (name, value) = match &node.kind {
+            NodeKind::Struct(declaration) => (
+                &declaration.name,
+                Value::Struct(Rc::new(struct_type(env, declaration))),
+            ),
+            NodeKind::Shared(declaration) => (
+                &declaration.name,
+                Value::Shared(Rc::new(SharedType {
+                    declaration: declaration.clone(),
+                    closure: env.clone(),
+                })),
+            ),
+            NodeKind::Enum(declaration) => (
+                &declaration.name,
+                Value::Enum(Rc::new(EnumType {
+                    declaration: declaration.clone(),
+                })),
+            ),
+            NodeKind::Impl(declaration) => return implement(env, declaration),
+            _ => unreachable!("only declarations are compiled to items"),
+        };
+        env.borrow_mut().define(name, value);
+        Ok(())
+    }
+}
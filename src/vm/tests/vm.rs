@@ -0,0 +1,24 @@
+//! Runs programs on the bytecode VM and checks the value they end with.
+
+use lexer::scanner::Scanner;
+use parser::parser::Parser;
+use vm::vm::Vm;
+
+/// Runs `source` on `vm` and prints the value of its last statement.
+fn run(vm: &mut Vm, source: &str) -> String {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens().unwrap();
+    let program = Parser::new(&scanner.tokens).parse_program().unwrap();
+    match vm.run(&program) {
+        Ok(value) => value.to_string(),
+        Err(error) => error.message.clone(),
+    }
+}
+
+#[test]
+fn defaults_of_each_program_are_their_own() {
+    // both defaults are at the same place in their source
+    let mut vm = Vm::new();
+    assert_eq!(run(&mut vm, "struct A { n: number = 1; } A {}.n"), "1");
+    assert_eq!(run(&mut vm, "struct B { n: number = 2; } B {}.n"), "2");
+}
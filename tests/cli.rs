@@ -0,0 +1,169 @@
+//! The `ion` binary: subcommands, exit codes and the REPL.
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs `ion` with `args`, feeding it `stdin`.
+fn ion(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ion"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run ion");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn missing_file_exits_with_an_io_error() {
+    let output = ion(&["run", "tests/no_such_file.ion"], "");
+    assert_eq!(output.status.code(), Some(66));
+    assert!(stderr(&output).contains("No such file or directory"));
+}
+
+#[test]
+fn syntax_errors_exit_with_data_error() {
+    let output = ion(&["run", "-"], "let x = ;");
+    assert_eq!(output.status.code(), Some(65));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn runtime_errors_exit_with_software_error() {
+    let output = ion(&["run", "-"], "println(1); 1 + nil;");
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(stdout(&output), "1\n");
+}
+
+#[test]
+fn repl_keeps_declarations_between_entries() {
+    let output = ion(&[], "let x = 2;\nfn f(a) {\n  a * x\n}\nf(21)\n");
+    assert_eq!(output.status.code(), Some(0));
+    // the function continues over three lines before its value is printed
+    assert!(stdout(&output).contains("> > ... ... > 42\n"));
+}
+
+#[test]
+fn repl_checks_entries_against_earlier_ones() {
+    let output = ion(
+        &[],
+        "struct W {}\nshared A { fn a(); }\nimpl shared A on W { fn a() { 7 } }\nW {}.a()\n",
+    );
+    assert_eq!(stderr(&output), "");
+    assert!(stdout(&output).ends_with("> 7\n> "));
+}
+
+#[test]
+fn repl_reports_errors_and_carries_on() {
+    let output = ion(&[], "let y = ;\n1 + nil\nlet y = 3;\ny * 2;\n:quit\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr(&output).contains("Unexpected token: `;`"));
+    assert!(stderr(&output).contains("Unsupported operand types for +: number and nil"));
+    assert!(stdout(&output).ends_with("> 6\n> "));
+}
+
+#[test]
+fn repl_shows_tokens() {
+    let output = ion(&[], ":tokens 1 + 2\n");
+    assert!(stdout(&output).contains("[LINE: 1, COL: 3] Operators(PLUS)\n"));
+}
+
+#[test]
+fn a_file_alone_is_run() {
+    let output = ion(&["tests/programs/interpreter.ion"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).starts_with("10 2 24 1.5\n"));
+}
+
+#[test]
+fn check_does_not_run_the_program() {
+    let output = ion(&["check", "-"], "println(\"hi\"); 1 + nil;");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+
+    let output = ion(&["check", "-"], "let x = ;");
+    assert_eq!(output.status.code(), Some(65));
+}
+
+#[test]
+fn tokens_prints_every_token() {
+    let output = ion(&["tokens", "-"], "let a = 1;");
+    assert_eq!(
+        stdout(&output),
+        "[LINE: 1, COL: 1] Identifiers(LET)\n\
+         [LINE: 1, COL: 5] Name(\"a\")\n\
+         [LINE: 1, COL: 7] Operators(EQUAL)\n\
+         [LINE: 1, COL: 9] Number(\"1\")\n\
+         [LINE: 1, COL: 10] Punctuation(SEMICOLON)\n\
+         [LINE: 1, COL: 11] EOF\n"
+    );
+}
+
+#[test]
+fn tokens_stop_quietly_when_the_reader_does() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ion"))
+        .args(["tokens", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run ion");
+    // far more tokens than a pipe holds, with nobody reading them
+    drop(child.stdout.take());
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("let a = 1;\n".repeat(20_000).as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(stderr(&output), "");
+    assert!(output.status.success());
+}
+
+#[test]
+fn ast_prints_the_tree_as_json() {
+    let output = ion(&["ast", "--json", "-"], "x + 1;");
+    assert_eq!(output.status.code(), Some(0));
+    let json = stdout(&output);
+    assert!(json.starts_with("[{\"kind\":\"BinaryExpr\",\"op\":\"+\","));
+    assert!(json.contains("{\"kind\":\"Name\",\"name\":\"x\","));
+}
+
+#[test]
+fn fmt_prints_the_standard_layout() {
+    let output = ion(&["fmt", "-"], "let a=1;// c\nfn f(x){x+a}\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "let a = 1;\n\nfn f(x) {\n    x + a\n}\n");
+    assert!(stderr(&output).contains("Comments are not kept by `ion fmt`"));
+
+    // formatting is stable
+    let again = ion(&["fmt", "-"], &stdout(&output));
+    assert_eq!(stdout(&again), stdout(&output));
+}
+
+#[test]
+fn unknown_options_exit_with_usage_error() {
+    let output = ion(&["--bogus"], "");
+    assert_eq!(output.status.code(), Some(64));
+    assert!(stderr(&output).contains("unknown option '--bogus'"));
+}
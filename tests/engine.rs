@@ -0,0 +1,139 @@
+//! Embedding Ion through `ion::Engine`.
+
+use std::error::Error;
+
+use error::types::ErrorTypes;
+use interpreter::convert::FromValue;
+use ion::Engine;
+use lexer::file_sys::file_to_string;
+
+#[test]
+fn syntax_errors_are_returned_to_the_host() {
+    let mut engine = Engine::new();
+    let errors = engine.run("let x = ;").unwrap_err();
+    assert_eq!(errors[0].error_type, ErrorTypes::ParsingError);
+
+    // the host keeps running and so does the engine
+    assert!(engine.run("let y = 1;").is_ok());
+}
+
+#[test]
+fn runtime_errors_are_returned_to_the_host() {
+    let mut engine = Engine::new();
+    let errors = engine.run("let x = 1 + nil;").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].error_type, ErrorTypes::RuntimeError);
+}
+
+#[test]
+fn compiler_errors_are_std_errors() {
+    let error = Engine::new().run("let s = 'ab';").unwrap_err().remove(0);
+    let error: Box<dyn Error> = Box::new(error);
+    assert!(error.to_string().contains("TokenizationError"));
+}
+
+#[test]
+fn unreadable_files_are_errors() {
+    let error = file_to_string("tests/no_such_file.ion").unwrap_err();
+    assert_eq!(error.error_type, ErrorTypes::FileIOError);
+    assert!(error.message.contains("tests/no_such_file.ion"));
+}
+
+#[test]
+fn later_runs_are_checked_against_earlier_declarations() {
+    let mut engine = Engine::new();
+    engine.run("struct W {}").unwrap();
+    engine.run("shared A { fn a() => number; }").unwrap();
+    engine
+        .run("impl shared A on W { fn a() => number { 1 } }")
+        .unwrap();
+    assert_eq!(engine.run("W {}.a()").unwrap().to_string(), "1");
+
+    engine.run("fn half(n: number) { n / 2 }").unwrap();
+    // reported by the checker, not by the call at runtime
+    let errors = engine.run("half(\"x\");").unwrap_err();
+    assert_eq!(errors[0].error_type, ErrorTypes::SemanticError);
+    assert_eq!(
+        errors[0].message,
+        "Mismatched types: expected number for parameter 'n', found string"
+    );
+}
+
+#[test]
+fn hosts_call_ion_functions() {
+    let mut engine = Engine::new();
+    engine.run("fn add(a, b) { return a + b; }").unwrap();
+    assert_eq!(engine.call::<f64>("add", (1, 2)).unwrap(), 3.0);
+    assert_eq!(engine.call::<String>("add", ("a", "b")).unwrap(), "ab");
+
+    // prelude functions are globals too
+    let parts: Vec<String> = engine.call("split", ("a,b", ",")).unwrap();
+    assert_eq!(parts, ["a", "b"]);
+}
+
+#[test]
+fn ion_calls_registered_functions() {
+    let mut engine = Engine::new();
+    engine.register("twice", Some(1), |args| {
+        Ok(f64::from_value(args[0].clone())? * 2.0)
+    });
+    engine.run("let out = twice(21);").unwrap();
+    assert_eq!(engine.get_global::<i64>("out").unwrap(), 42);
+
+    // a failing host function is a runtime error at the call site
+    let errors = engine.run("let bad = twice(\"x\");").unwrap_err();
+    assert_eq!(errors[0].error_type, ErrorTypes::RuntimeError);
+    assert_eq!(errors[0].col, 11);
+
+    let errors = engine.run("twice(1, 2);").unwrap_err();
+    assert!(errors[0].message.contains("expects 1 argument(s)"));
+}
+
+#[test]
+fn globals_move_between_host_and_ion() {
+    let mut engine = Engine::new();
+    engine.set_global("base", 10);
+    engine.set_global("names", vec!["a", "b"]);
+    engine.run("let total = base + len(names);").unwrap();
+    assert_eq!(engine.get_global::<f64>("total").unwrap(), 12.0);
+    assert!(engine.get_global::<f64>("missing").is_err());
+}
+
+#[test]
+fn conversions_report_what_they_found() {
+    let mut engine = Engine::new();
+    engine.run("let half = 0.5; let name = \"ion\";").unwrap();
+    let error = engine.get_global::<i64>("half").unwrap_err();
+    assert_eq!(error.message, "Expected an integer, found number");
+    assert!(engine.get_global::<bool>("name").is_err());
+
+    let error = engine.call::<f64>("nope", ()).unwrap_err();
+    assert_eq!(error.message, "Undefined global 'nope'");
+}
+
+#[test]
+fn undefined_globals_point_at_the_host_code_asking_for_them() {
+    let mut engine = Engine::new();
+    let line = line!() + 1;
+    let error = engine.call::<f64>("nope", ()).unwrap_err();
+    assert_eq!(
+        (error.location.as_str(), error.line),
+        (file!(), line as usize)
+    );
+    let line = line!() + 1;
+    let error = engine.get_global::<f64>("nope").unwrap_err();
+    assert_eq!(
+        (error.location.as_str(), error.line),
+        (file!(), line as usize)
+    );
+}
+
+#[test]
+fn deep_recursion_is_an_error_on_a_default_thread() {
+    // test threads get the default stack size, not the CLI's large one
+    let mut engine = Engine::new();
+    let errors = engine.run("fn f(n) { f(n + 1) } f(0);").unwrap_err();
+    assert_eq!(errors[0].message, "Stack overflow");
+    let shallow = "fn g(n) { if n == 0 { 0 } else { g(n - 1) } } g(20);";
+    assert!(engine.run(shallow).is_ok());
+}
@@ -0,0 +1,92 @@
+//! Runs every program in `tests/programs` with `ion run` and compares what it
+//! printed, and how it exited, with the `.out` file next to it. Set
+//! `ION_BLESS=1` to write the current output as the expected one instead.
+//! The same programs also have to behave identically on the bytecode VM.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Everything a run of `ion` shows: stdout, then stderr and the exit code
+/// when there are any.
+fn transcript(args: &[&str], program: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_ion"))
+        .args(args)
+        .arg(program)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("failed to run ion");
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.is_empty() {
+        text.push_str("--- stderr\n");
+        text.push_str(&stderr);
+    }
+    match output.status.code() {
+        Some(0) => {}
+        Some(code) => text.push_str(&format!("--- exit {code}\n")),
+        None => text.push_str("--- killed by a signal\n"),
+    }
+    text
+}
+
+/// The programs to run, relative to the crate root so diagnostics print
+/// the same path wherever the repository is checked out.
+fn programs() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut programs: Vec<PathBuf> = fs::read_dir(root.join("tests/programs"))
+        .expect("tests/programs is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "ion"))
+        .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+        .collect();
+    programs.sort();
+    programs
+}
+
+#[test]
+fn programs_print_what_they_should() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bless = env::var_os("ION_BLESS").is_some();
+    let mut failures = vec![];
+
+    for program in programs() {
+        let actual = transcript(&["run"], &program);
+        let expected_path = root.join(program.with_extension("out"));
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual != expected {
+            failures.push(format!(
+                "{}\n--- expected\n{expected}--- actual\n{actual}",
+                program.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn backends_agree() {
+    let mut failures = vec![];
+
+    for program in programs().into_iter().chain([PathBuf::from("test.ion")]) {
+        let tree = transcript(&["run"], &program);
+        let vm = transcript(&["run", "--backend=vm"], &program);
+        if tree != vm {
+            failures.push(format!(
+                "{}\n--- tree-walker\n{tree}--- vm\n{vm}",
+                program.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}